@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use larix::parse;
+
+/** Builds a document nested `depth` levels deep, with some text at every level. */
+fn deep(depth: usize) -> String {
+    let mut xml = String::new();
+    for _ in 0..depth {
+        xml.push_str(r#"<node attr="value">text"#);
+    }
+    for _ in 0..depth {
+        xml.push_str("</node>");
+    }
+    xml
+}
+
+/** Builds a document with `count` sibling elements below a single root. */
+fn wide(count: usize) -> String {
+    let mut xml = String::from("<root>");
+    for _ in 0..count {
+        xml.push_str(r#"<node attr="value">text</node>"#);
+    }
+    xml.push_str("</root>");
+    xml
+}
+
+fn bench_parse(c: &mut Criterion) {
+    for (name, generate) in [("deep", deep as fn(usize) -> String), ("wide", wide)] {
+        let mut group = c.benchmark_group(name);
+        // Large enough for time growing faster than the input to stand out.
+        group.sample_size(10);
+        for size in [10_000, 25_000, 50_000, 100_000, 200_000] {
+            let xml = generate(size);
+            group.throughput(Throughput::Bytes(xml.len() as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &xml, |b, xml| {
+                b.iter(|| parse(black_box(xml)).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,224 @@
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
+
+use crate::namespace::split_name;
+
+/** Attribute ```name="value"``` of an element. */
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Attribute {
+    /** Name of the attribute, including its prefix. */
+    pub name: String,
+    /** Value of the attribute. */
+    pub value: String,
+    /** Namespace of the attribute, if resolved. */
+    pub(crate) namespace: Option<String>,
+}
+
+impl Attribute {
+    pub fn new(name: String, value: String) -> Self {
+        Attribute {
+            name,
+            value,
+            namespace: None,
+        }
+    }
+
+    /** Get the prefix of the name, e.g. ```xlink``` for ```xlink:href```. */
+    pub fn prefix(&self) -> Option<&str> {
+        split_name(&self.name).0
+    }
+
+    /** Get the name without its prefix, e.g. ```href``` for ```xlink:href```. */
+    pub fn local_name(&self) -> &str {
+        split_name(&self.name).1
+    }
+
+    /** Get the namespace URI of the attribute.
+
+    Namespaces are only resolved if enabled through [`ParseOptions::namespaces`](crate::ParseOptions::namespaces).
+    Attributes without a prefix are in no namespace. */
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+}
+
+/** Attributes of an element, in the order they were added or appeared in the input.
+
+Lookups by name work like those of a map. Elements rarely have many attributes,
+so the attributes are stored in a list and searched linearly.
+```rust
+# use larix::*;
+let mut attributes = Attributes::new();
+attributes.insert(String::from("b"), String::from("1"));
+attributes.insert(String::from("a"), String::from("2"));
+attributes.insert(String::from("b"), String::from("3"));
+
+assert_eq!(attributes.get("b").unwrap(), "3");
+let names: Vec<&String> = attributes.keys().collect();
+assert_eq!(names, ["b", "a"]);
+
+// Comparisons do not depend on the order.
+let mut reordered = Attributes::new();
+reordered.insert(String::from("a"), String::from("2"));
+reordered.insert(String::from("b"), String::from("3"));
+assert_eq!(attributes, reordered);
+```*/
+#[derive(Clone, Default)]
+pub struct Attributes {
+    pub(crate) list: Vec<Attribute>,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Attributes { list: Vec::new() }
+    }
+
+    /** Get the number of attributes. */
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /** Whether there are no attributes. */
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /** Get the value of the attribute with the given name. */
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.list
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| &attr.value)
+    }
+
+    /** Get the value of the attribute with the given name for modification. */
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut String> {
+        self.list
+            .iter_mut()
+            .find(|attr| attr.name == name)
+            .map(|attr| &mut attr.value)
+    }
+
+    /** Whether there is an attribute with the given name. */
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.list.iter().any(|attr| attr.name == name)
+    }
+
+    /** Set the value of an attribute, returning its previous value.
+
+    An existing attribute keeps its position, a new one is added at the end. */
+    pub fn insert(&mut self, name: String, value: String) -> Option<String> {
+        match self.get_mut(&name) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.list.push(Attribute::new(name, value));
+                None
+            }
+        }
+    }
+
+    /** Remove an attribute, returning its value. The remaining attributes keep their order. */
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.list.iter().position(|attr| attr.name == name)?;
+        Some(self.list.remove(index).value)
+    }
+
+    /** Get the value of the attribute with the given namespace and local name. */
+    pub fn get_ns(&self, namespace: &str, local_name: &str) -> Option<&String> {
+        self.list
+            .iter()
+            .find(|attr| attr.namespace() == Some(namespace) && attr.local_name() == local_name)
+            .map(|attr| &attr.value)
+    }
+
+    /** Remove all attributes. */
+    pub fn clear(&mut self) {
+        self.list.clear()
+    }
+
+    /** Iterate over the names and values of all attributes, in order. */
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.list.iter().map(|attr| (&attr.name, &attr.value))
+    }
+
+    /** Iterate over the names of all attributes, in order. */
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.list.iter().map(|attr| &attr.name)
+    }
+
+    /** Iterate over the values of all attributes, in order. */
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.list.iter().map(|attr| &attr.value)
+    }
+
+    /** Get all attributes, in order. */
+    pub fn as_slice(&self) -> &[Attribute] {
+        &self.list
+    }
+}
+
+impl PartialEq for Attributes {
+    /** Whether both have the same attributes, regardless of their order. */
+    fn eq(&self, other: &Self) -> bool {
+        // Names are unique, so each attribute has at most one counterpart.
+        self.len() == other.len()
+            && self.list.iter().all(|attr| {
+                let counterpart = other.list.iter().find(|other| other.name == attr.name);
+                counterpart == Some(attr)
+            })
+    }
+}
+
+impl Eq for Attributes {}
+
+impl Hash for Attributes {
+    /** Hash the attributes sorted by name, so that the order does not matter. */
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut sorted: Vec<&Attribute> = self.list.iter().collect();
+        sorted.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        sorted.hash(state);
+    }
+}
+
+impl Debug for Attributes {
+    /** Write the attributes as a map from names to values. */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a Attributes {
+    type Item = &'a Attribute;
+    type IntoIter = std::slice::Iter<'a, Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+impl IntoIterator for Attributes {
+    type Item = Attribute;
+    type IntoIter = std::vec::IntoIter<Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+impl FromIterator<(String, String)> for Attributes {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        let mut attributes = Attributes::new();
+        attributes.extend(iter);
+        attributes
+    }
+}
+
+impl Extend<(String, String)> for Attributes {
+    fn extend<T: IntoIterator<Item = (String, String)>>(&mut self, iter: T) {
+        for (name, value) in iter {
+            self.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    pi::{is_space, pseudo_attributes},
+    DeclError, Item,
+};
+
+/** XML declaration ```<?xml version="1.0" encoding="UTF-8" standalone="yes"?>```.
+
+It can be parsed from the content of an [`Item::Decl`], and turned back into one.
+Its parts are validated when set, so it can always be written back as it is.
+```rust
+# use larix::*;
+let items = parse(r#"<?xml version="1.0" encoding="ISO-8859-1"?><a />"#)?;
+
+let mut decl = XmlDecl::try_from(&items[0])?;
+assert_eq!(decl.encoding(), Some("ISO-8859-1"));
+
+decl.set_encoding(Some(String::from("UTF-8")))?;
+assert_eq!(Item::from(decl.clone()).to_string(), r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+
+assert!(decl.set_encoding(Some(String::from("a\"?><evil/>"))).is_err());
+# Ok::<(), Box<dyn std::error::Error>>(())
+```*/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct XmlDecl {
+    version: String,
+    encoding: Option<String>,
+    standalone: Option<bool>,
+}
+
+impl XmlDecl {
+    /** Create a declaration of the given version, without encoding or standalone, checking that the version is valid. */
+    pub fn new(version: String) -> Result<Self, DeclError> {
+        check_version(&version)?;
+        Ok(XmlDecl {
+            version,
+            encoding: None,
+            standalone: None,
+        })
+    }
+
+    /** Get the version of XML, e.g. ```1.0```. */
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /** Set the version of XML, checking that it is of the form ```1.x```. */
+    pub fn set_version(&mut self, version: String) -> Result<(), DeclError> {
+        check_version(&version)?;
+        self.version = version;
+        Ok(())
+    }
+
+    /** Get the name of the character encoding, e.g. ```UTF-8```. */
+    pub fn encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    /** Set the name of the character encoding, checking that it is a valid encoding name. */
+    pub fn set_encoding(&mut self, encoding: Option<String>) -> Result<(), DeclError> {
+        if let Some(encoding) = &encoding {
+            check_encoding(encoding)?;
+        }
+        self.encoding = encoding;
+        Ok(())
+    }
+
+    /** Get whether the document does not depend on external markup declarations. */
+    pub fn standalone(&self) -> Option<bool> {
+        self.standalone
+    }
+
+    /** Set whether the document does not depend on external markup declarations. */
+    pub fn set_standalone(&mut self, standalone: Option<bool>) {
+        self.standalone = standalone;
+    }
+}
+
+impl Default for XmlDecl {
+    /** Declaration of version ```1.0```. */
+    fn default() -> Self {
+        XmlDecl {
+            version: String::from("1.0"),
+            encoding: None,
+            standalone: None,
+        }
+    }
+}
+
+impl FromStr for XmlDecl {
+    type Err = DeclError;
+
+    /** Parse the content of a declaration, i.e. everything between ```<?``` and ```?>```. */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("xml").ok_or(DeclError::Malformed)?;
+        if !rest.is_empty() && !rest.starts_with(is_space) {
+            return Err(DeclError::Malformed);
+        }
+        let mut attributes = pseudo_attributes(rest)
+            .ok_or(DeclError::Malformed)?
+            .into_iter()
+            .peekable();
+
+        let version = match attributes.next() {
+            Some(("version", version)) => version,
+            _ => return Err(DeclError::MissingVersion),
+        };
+        let mut decl = XmlDecl::new(version.to_owned())?;
+
+        if let Some((_, encoding)) = attributes.next_if(|(name, _)| *name == "encoding") {
+            decl.set_encoding(Some(encoding.to_owned()))?;
+        }
+
+        if let Some((_, standalone)) = attributes.next_if(|(name, _)| *name == "standalone") {
+            decl.standalone = Some(match standalone {
+                "yes" => true,
+                "no" => false,
+                _ => return Err(DeclError::InvalidStandalone(standalone.to_owned())),
+            });
+        }
+
+        match attributes.next() {
+            Some(_) => Err(DeclError::Malformed),
+            None => Ok(decl),
+        }
+    }
+}
+
+impl TryFrom<&Item> for XmlDecl {
+    type Error = DeclError;
+
+    fn try_from(item: &Item) -> Result<Self, Self::Error> {
+        match item {
+            Item::Decl(decl) => decl.parse(),
+            _ => Err(DeclError::Malformed),
+        }
+    }
+}
+
+impl From<XmlDecl> for Item {
+    fn from(decl: XmlDecl) -> Self {
+        Item::Decl(decl.to_string())
+    }
+}
+
+impl Display for XmlDecl {
+    /** Write the content of the declaration, i.e. everything between ```<?``` and ```?>```. */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "xml version=\"{}\"", self.version)?;
+        if let Some(encoding) = &self.encoding {
+            write!(f, " encoding=\"{encoding}\"")?;
+        }
+        if let Some(standalone) = self.standalone {
+            let standalone = if standalone { "yes" } else { "no" };
+            write!(f, " standalone=\"{standalone}\"")?;
+        }
+        Ok(())
+    }
+}
+
+fn check_version(version: &str) -> Result<(), DeclError> {
+    if !is_version(version) {
+        return Err(DeclError::InvalidVersion(version.to_owned()));
+    }
+    Ok(())
+}
+
+fn check_encoding(encoding: &str) -> Result<(), DeclError> {
+    if !is_encoding(encoding) {
+        return Err(DeclError::InvalidEncoding(encoding.to_owned()));
+    }
+    Ok(())
+}
+
+/** Whether the version matches ```1.[0-9]+```. */
+fn is_version(version: &str) -> bool {
+    match version.strip_prefix("1.") {
+        Some(minor) => !minor.is_empty() && minor.bytes().all(|b| b.is_ascii_digit()),
+        None => false,
+    }
+}
+
+/** Whether the encoding matches ```[A-Za-z][A-Za-z0-9._-]*```. */
+fn is_encoding(encoding: &str) -> bool {
+    let mut bytes = encoding.bytes();
+    matches!(bytes.next(), Some(b) if b.is_ascii_alphabetic())
+        && bytes.all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
@@ -0,0 +1,214 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{pi::is_space, DocTypeError};
+
+/** Document type declaration ```<!DOCTYPE name PUBLIC "public id" "system id" [internal subset]>```.
+
+Whitespace within the declaration is not kept. It is written back with single spaces.
+The literals of the external ID are checked when set, as some could not be quoted at all.
+The name and the internal subset are not checked, so they have to be well-formed to be written back correctly.
+```rust
+# use larix::*;
+let xml = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN"
+    "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"><html />"#;
+
+let mut document = parse_document(xml)?;
+let doctype = document.doctype_mut().unwrap();
+assert_eq!(doctype.name, "html");
+
+doctype.set_external_id(Some(ExternalId::Public {
+    public: String::from("-//W3C//DTD XHTML 1.0 Transitional//EN"),
+    system: String::from("http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd"),
+}))?;
+assert_eq!(
+    document.to_string(),
+    r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd"><html />"#
+);
+
+let quotes = ExternalId::System {
+    system: String::from(r#"both "quotes" and 'quotes'.dtd"#),
+};
+let doctype = document.doctype_mut().unwrap();
+assert_eq!(doctype.set_external_id(Some(quotes)), Err(DocTypeError::InvalidLiteral));
+# Ok::<(), Box<dyn std::error::Error>>(())
+```*/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DocType {
+    /** Name of the root element. Written as it is, so it must be an XML name. */
+    pub name: String,
+    external_id: Option<ExternalId>,
+    /** Markup declarations between ```[``` and ```]```, exactly as written.
+
+    When parsing, the internal entities declared here are expanded in text and attribute values,
+    and default attribute values are added to elements lacking them.
+    Replacement texts are inserted as text, so markup within them is not parsed.
+    Parameter entities and external entities are ignored. */
+    pub internal_subset: Option<String>,
+}
+
+/** Reference to an external DTD. */
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ExternalId {
+    /** ```SYSTEM "system id"```. */
+    System {
+        /** URI of the DTD. */
+        system: String,
+    },
+    /** ```PUBLIC "public id" "system id"```. */
+    Public {
+        /** Public identifier of the DTD. */
+        public: String,
+        /** URI of the DTD. */
+        system: String,
+    },
+}
+
+impl DocType {
+    /** Create a DOCTYPE with the given root element name, without external ID or internal subset. */
+    pub fn new(name: String) -> Self {
+        DocType {
+            name,
+            external_id: None,
+            internal_subset: None,
+        }
+    }
+
+    /** Get the reference to the external subset, if any. */
+    pub fn external_id(&self) -> Option<&ExternalId> {
+        self.external_id.as_ref()
+    }
+
+    /** Set the reference to the external subset, checking that its literals can be quoted.
+
+    Literals cannot contain both ```"``` and ```'```, as there would be no way to quote them. */
+    pub fn set_external_id(&mut self, external_id: Option<ExternalId>) -> Result<(), DocTypeError> {
+        match &external_id {
+            Some(ExternalId::System { system }) => check_literal(system)?,
+            Some(ExternalId::Public { public, system }) => {
+                check_literal(public)?;
+                check_literal(system)?;
+            }
+            None => (),
+        }
+        self.external_id = external_id;
+        Ok(())
+    }
+
+    /** Get the public identifier, if any. */
+    pub fn public_id(&self) -> Option<&str> {
+        match &self.external_id {
+            Some(ExternalId::Public { public, .. }) => Some(public),
+            _ => None,
+        }
+    }
+
+    /** Get the system identifier, if any. */
+    pub fn system_id(&self) -> Option<&str> {
+        match &self.external_id {
+            Some(ExternalId::System { system } | ExternalId::Public { system, .. }) => Some(system),
+            None => None,
+        }
+    }
+}
+
+impl FromStr for DocType {
+    type Err = DocTypeError;
+
+    /** Parse the content of a DOCTYPE, i.e. everything between ```<!DOCTYPE``` and ```>```. */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.trim_start_matches(is_space);
+        let end = rest.find(|c| is_space(c) || c == '[').unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(end);
+        if name.is_empty() {
+            return Err(DocTypeError::MissingName);
+        }
+        let mut doctype = DocType::new(name.to_owned());
+
+        let mut rest = rest.trim_start_matches(is_space);
+        if let Some(after) = rest.strip_prefix("SYSTEM") {
+            let (system, after) = literal(after)?;
+            doctype.external_id = Some(ExternalId::System { system });
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("PUBLIC") {
+            let (public, after) = literal(after)?;
+            let (system, after) = literal(after)?;
+            doctype.external_id = Some(ExternalId::Public { public, system });
+            rest = after;
+        }
+
+        let rest = rest.trim_start_matches(is_space);
+        if let Some(after) = rest.strip_prefix('[') {
+            // Brackets may also occur within the subset, e.g. in comments, so the last one ends it.
+            let end = after
+                .rfind(']')
+                .ok_or(DocTypeError::UnclosedInternalSubset)?;
+            if !after[end + 1..].chars().all(is_space) {
+                return Err(DocTypeError::TrailingContent);
+            }
+            doctype.internal_subset = Some(after[..end].to_owned());
+        } else if !rest.is_empty() {
+            return Err(DocTypeError::TrailingContent);
+        }
+
+        Ok(doctype)
+    }
+}
+
+impl Display for DocType {
+    /** Write the content of the DOCTYPE, i.e. everything between ```<!DOCTYPE``` and ```>```. */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)?;
+        match &self.external_id {
+            Some(ExternalId::System { system }) => {
+                f.write_str(" SYSTEM ")?;
+                write_literal(f, system)?;
+            }
+            Some(ExternalId::Public { public, system }) => {
+                f.write_str(" PUBLIC ")?;
+                write_literal(f, public)?;
+                f.write_str(" ")?;
+                write_literal(f, system)?;
+            }
+            None => (),
+        }
+        if let Some(subset) = &self.internal_subset {
+            write!(f, " [{subset}]")?;
+        }
+        Ok(())
+    }
+}
+
+/** Read a quoted literal preceded by whitespace, returning its content and the rest of the input. */
+fn literal(s: &str) -> Result<(String, &str), DocTypeError> {
+    let trimmed = s.trim_start_matches(is_space);
+    if trimmed.len() == s.len() {
+        return Err(DocTypeError::MalformedExternalId);
+    }
+
+    let quote = trimmed
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\''))
+        .ok_or(DocTypeError::MalformedExternalId)?;
+    let (value, rest) = trimmed[1..]
+        .split_once(quote)
+        .ok_or(DocTypeError::MalformedExternalId)?;
+
+    Ok((value.to_owned(), rest))
+}
+
+fn check_literal(value: &str) -> Result<(), DocTypeError> {
+    if value.contains('"') && value.contains('\'') {
+        return Err(DocTypeError::InvalidLiteral);
+    }
+    Ok(())
+}
+
+/** Write a literal, quoted with single quotes if it contains double quotes. */
+fn write_literal(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    if value.contains('"') {
+        write!(f, "'{value}'")
+    } else {
+        write!(f, "\"{value}\"")
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt::Display;
+
+use crate::{
+    serialize::Serializer, util::parse_str, DeclError, DocType, DocumentError, Element, Error,
+    Item, ParseOptions, Position, SerializeOptions, Span, XmlDecl,
+};
+
+/** XML document, consisting of exactly one root element and the items around it.
+
+The items before the root element make up the prolog, e.g. the XML declaration, the DOCTYPE and comments.
+The items after it make up the epilog. Whitespace between them is kept, so the document is written back as it was read.
+```rust
+# use larix::*;
+let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE config>
+<config><server port="80" /></config>
+<!-- end of config -->"#;
+
+let document = parse_document(xml)?;
+
+assert_eq!(document.decl(), Some(r#"xml version="1.0""#));
+assert_eq!(document.doctype().unwrap().name, "config");
+assert_eq!(document.root().name, "config");
+assert!(matches!(document.epilog().last(), Some(Item::Comment(_))));
+assert_eq!(document.to_string(), xml);
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Document {
+    prolog: Vec<Item>,
+    root: Element,
+    epilog: Vec<Item>,
+}
+
+/** Parse an XML document, which must contain exactly one root element.
+
+Text and CDATA are not allowed outside of the root element, apart from whitespace.
+The XML declaration has to come first, and there may be one DOCTYPE before the root element.
+Violations are reported as [`Error::Document`]. */
+pub fn parse_document(value: &str) -> Result<Document, Error> {
+    parse_document_with(value, &ParseOptions::new())
+}
+
+/** Parse an XML document using the given options. See [`parse_document`]. */
+pub fn parse_document_with(value: &str, options: &ParseOptions) -> Result<Document, Error> {
+    let items = parse_str(value, options, true)?.items;
+
+    // The parser has already checked the structure, so this only fails if it is mistaken.
+    Document::from_items(items).map_err(|reason| {
+        let offset = value.len() as u64;
+        let span = Span {
+            start: offset,
+            end: offset,
+        };
+        let (line, column) = span.line_column(value);
+        Error::Document {
+            reason,
+            position: Position {
+                offset,
+                line,
+                column,
+                path: String::from("/"),
+            },
+        }
+    })
+}
+
+impl Document {
+    /** Create a document consisting of only the given root element. */
+    pub fn new(root: Element) -> Self {
+        Document {
+            prolog: Vec::new(),
+            root,
+            epilog: Vec::new(),
+        }
+    }
+
+    /** Get the content of the XML declaration, e.g. ```xml version="1.0"```. */
+    pub fn decl(&self) -> Option<&str> {
+        self.prolog.iter().find_map(|item| match item {
+            Item::Decl(decl) => Some(decl.as_str()),
+            _ => None,
+        })
+    }
+
+    /** Create a document from a list of items, e.g. as returned by [`parse_reader`](crate::parse_reader).
+
+    Fails if the items do not contain exactly one element, or contain text or CDATA besides whitespace outside of it.
+    Also fails if there is an XML declaration which is not the first item, or a DOCTYPE which is not the only one or comes after the element. */
+    pub fn from_items(items: Vec<Item>) -> Result<Self, DocumentError> {
+        let mut prolog = Vec::new();
+        let mut root = None;
+        let mut epilog = Vec::new();
+
+        for (index, item) in items.into_iter().enumerate() {
+            match item {
+                Item::Decl(_) if index > 0 => return Err(DocumentError::MisplacedDecl),
+                Item::DocType(_) if root.is_some() => return Err(DocumentError::MisplacedDocType),
+                Item::DocType(_) if prolog.iter().any(|item| matches!(item, Item::DocType(_))) => {
+                    return Err(DocumentError::MultipleDocTypes)
+                }
+                Item::Element(element) if root.is_none() => root = Some(element),
+                Item::Element(_) => return Err(DocumentError::MultipleRoots),
+                Item::Text(text) if !text.chars().all(char::is_whitespace) => {
+                    return Err(DocumentError::TextOutsideRoot)
+                }
+                Item::CData(_) => return Err(DocumentError::TextOutsideRoot),
+                item if root.is_none() => prolog.push(item),
+                item => epilog.push(item),
+            }
+        }
+
+        Ok(Document {
+            prolog,
+            root: root.ok_or(DocumentError::MissingRoot)?,
+            epilog,
+        })
+    }
+
+    /** Get the XML declaration, parsed into its parts. */
+    pub fn xml_decl(&self) -> Result<Option<XmlDecl>, DeclError> {
+        self.decl().map(str::parse).transpose()
+    }
+
+    /** Set the XML declaration, replacing the existing one or adding it at the start of the document. */
+    pub fn set_xml_decl(&mut self, decl: XmlDecl) {
+        match self
+            .prolog
+            .iter_mut()
+            .find(|item| matches!(item, Item::Decl(_)))
+        {
+            Some(item) => *item = decl.into(),
+            None => self.prolog.insert(0, decl.into()),
+        }
+    }
+
+    /** Get the DOCTYPE. */
+    pub fn doctype(&self) -> Option<&DocType> {
+        self.prolog.iter().find_map(|item| match item {
+            Item::DocType(doctype) => Some(doctype),
+            _ => None,
+        })
+    }
+
+    /** Get the DOCTYPE for modification. */
+    pub fn doctype_mut(&mut self) -> Option<&mut DocType> {
+        self.prolog.iter_mut().find_map(|item| match item {
+            Item::DocType(doctype) => Some(doctype),
+            _ => None,
+        })
+    }
+
+    /** Get the root element. */
+    pub fn root(&self) -> &Element {
+        &self.root
+    }
+
+    /** Get the root element for modification. */
+    pub fn root_mut(&mut self) -> &mut Element {
+        &mut self.root
+    }
+
+    /** Get the items before the root element. */
+    pub fn prolog(&self) -> &[Item] {
+        &self.prolog
+    }
+
+    /** Get the items before the root element for modification.
+
+    Only declarations, DOCTYPEs, comments, processing instructions and whitespace belong here. */
+    pub fn prolog_mut(&mut self) -> &mut Vec<Item> {
+        &mut self.prolog
+    }
+
+    /** Get the items after the root element. */
+    pub fn epilog(&self) -> &[Item] {
+        &self.epilog
+    }
+
+    /** Get the items after the root element for modification.
+
+    Only comments, processing instructions and whitespace belong here. */
+    pub fn epilog_mut(&mut self) -> &mut Vec<Item> {
+        &mut self.epilog
+    }
+
+    /** Turn the document into the list of its items, in order. */
+    pub fn into_items(self) -> Vec<Item> {
+        let mut items = self.prolog;
+        items.push(Item::Element(self.root));
+        items.extend(self.epilog);
+        items
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Serializer::new(f, &SerializeOptions::new()).document(self)
+    }
+}
@@ -0,0 +1,270 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use quick_xml::{
+    escape::{resolve_xml_entity, unescape, EscapeError},
+    Error as XmlError,
+};
+
+use crate::{error::Cause, pi::is_space, DocTypeError, Limit, ParseOptions};
+
+/** Declarations from the internal subset of a DOCTYPE which affect how the document is read.
+
+Only internal general entities and attribute defaults are taken into account.
+Parameter entities and external entities are neither resolved nor loaded. */
+#[derive(Default)]
+pub(crate) struct Dtd {
+    /** Replacement texts of internal general entities, with character references already decoded. */
+    entities: HashMap<String, String>,
+    /** Default values of attributes, as written, by element name. */
+    defaults: HashMap<String, Vec<(String, String)>>,
+}
+
+/** Part of a markup declaration like ```<!ATTLIST img alt CDATA #IMPLIED>```. */
+enum Token<'a> {
+    /** Name or keyword, e.g. ```img``` or ```#IMPLIED```. */
+    Word(&'a str),
+    /** Content of a quoted literal. */
+    Literal(&'a str),
+    /** Parenthesized group, e.g. an enumeration of values. */
+    Group,
+}
+
+impl Dtd {
+    /** Read the declarations of an internal subset. */
+    pub(crate) fn parse(subset: &str) -> Result<Self, DocTypeError> {
+        let mut dtd = Dtd::default();
+        let mut rest = subset;
+
+        loop {
+            rest = rest.trim_start_matches(is_space);
+            if rest.is_empty() {
+                return Ok(dtd);
+            }
+
+            rest = if let Some(after) = rest.strip_prefix("<!--") {
+                skip_past(after, "-->")?
+            } else if let Some(after) = rest.strip_prefix("<?") {
+                skip_past(after, "?>")?
+            } else if let Some(after) = rest.strip_prefix('%') {
+                // Parameter entities are not resolved, so their references are skipped.
+                skip_past(after, ";")?
+            } else if let Some(after) = rest.strip_prefix("<!") {
+                let (tokens, after) = declaration(after)?;
+                match tokens.as_slice() {
+                    [Token::Word("ENTITY"), tokens @ ..] => dtd.entity(tokens)?,
+                    [Token::Word("ATTLIST"), tokens @ ..] => dtd.attlist(tokens)?,
+                    [Token::Word("ELEMENT" | "NOTATION"), ..] => (),
+                    _ => return Err(DocTypeError::MalformedDeclaration),
+                }
+                after
+            } else {
+                return Err(DocTypeError::MalformedDeclaration);
+            };
+        }
+    }
+
+    /** Read the rest of an ```<!ENTITY>``` declaration. The first declaration of an entity is binding. */
+    fn entity(&mut self, tokens: &[Token]) -> Result<(), DocTypeError> {
+        match tokens {
+            [Token::Word("%"), ..] => Ok(()),
+            [Token::Word(name), Token::Literal(value)] => {
+                if !self.entities.contains_key(*name) {
+                    self.entities
+                        .insert((*name).to_owned(), decode_char_refs(value)?);
+                }
+                Ok(())
+            }
+            [Token::Word(_), Token::Word("SYSTEM" | "PUBLIC"), ..] => Ok(()),
+            _ => Err(DocTypeError::MalformedDeclaration),
+        }
+    }
+
+    /** Read the rest of an ```<!ATTLIST>``` declaration. The first declaration of an attribute is binding. */
+    fn attlist(&mut self, tokens: &[Token]) -> Result<(), DocTypeError> {
+        let [Token::Word(element), attributes @ ..] = tokens else {
+            return Err(DocTypeError::MalformedDeclaration);
+        };
+
+        let mut rest = attributes;
+        while let [Token::Word(name), after @ ..] = rest {
+            let after = match after {
+                [Token::Word("NOTATION"), Token::Group, after @ ..]
+                | [Token::Group, after @ ..]
+                | [Token::Word(_), after @ ..] => after,
+                _ => return Err(DocTypeError::MalformedDeclaration),
+            };
+            let (default, after) = match after {
+                [Token::Word("#REQUIRED" | "#IMPLIED"), after @ ..] => (None, after),
+                [Token::Word("#FIXED"), Token::Literal(value), after @ ..]
+                | [Token::Literal(value), after @ ..] => (Some(*value), after),
+                _ => return Err(DocTypeError::MalformedDeclaration),
+            };
+
+            if let Some(value) = default {
+                let defaults = self.defaults.entry((*element).to_owned()).or_default();
+                if !defaults.iter().any(|(attr, _)| attr == name) {
+                    defaults.push(((*name).to_owned(), value.to_owned()));
+                }
+            }
+            rest = after;
+        }
+
+        match rest {
+            [] => Ok(()),
+            _ => Err(DocTypeError::MalformedDeclaration),
+        }
+    }
+
+    /** Get the declared default values of the attributes of an element, as written. */
+    pub(crate) fn defaults(&self, element: &str) -> &[(String, String)] {
+        self.defaults.get(element).map_or(&[], Vec::as_slice)
+    }
+
+    /** Decode entity and character references, expanding declared entities.
+
+    Replacement texts are inserted as text. Markup within them is not parsed.
+    The number of bytes inserted is added to `expanded`, which must stay within the limit of the options. */
+    pub(crate) fn expand<'r>(
+        &self,
+        raw: &'r str,
+        options: &ParseOptions,
+        expanded: &mut usize,
+    ) -> Result<Cow<'r, str>, Cause> {
+        if self.entities.is_empty() {
+            return Ok(unescape(raw).map_err(XmlError::from)?);
+        }
+        if !raw.contains('&') {
+            return Ok(Cow::Borrowed(raw));
+        }
+
+        let mut out = String::with_capacity(raw.len());
+        self.expand_into(raw, &mut out, &mut Vec::new(), options, expanded)?;
+        Ok(Cow::Owned(out))
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        raw: &'a str,
+        out: &mut String,
+        open: &mut Vec<&'a str>,
+        options: &ParseOptions,
+        expanded: &mut usize,
+    ) -> Result<(), Cause> {
+        let mut rest = raw;
+
+        while let Some(start) = rest.find('&') {
+            out.push_str(&rest[..start]);
+            let offset = raw.len() - rest.len() + start;
+            let reference = &rest[start..];
+            let Some(end) = reference.find(';') else {
+                let range = offset..raw.len();
+                return Err(XmlError::from(EscapeError::UnterminatedEntity(range)).into());
+            };
+            let name = &reference[1..end];
+
+            if name.starts_with('#') {
+                out.push_str(&unescape(&reference[..=end]).map_err(XmlError::from)?);
+            } else if let Some(value) = resolve_xml_entity(name) {
+                out.push_str(value);
+            } else if let Some((name, value)) = self.entities.get_key_value(name) {
+                if open.contains(&name.as_str()) {
+                    return Err(Cause::MalformedDocType(DocTypeError::RecursiveEntity));
+                }
+                if open.len() >= options.max_entity_depth {
+                    return Err(Cause::Limit(Limit::EntityDepth));
+                }
+                *expanded = expanded.saturating_add(value.len());
+                if *expanded > options.max_entity_expansion {
+                    return Err(Cause::Limit(Limit::EntityExpansion));
+                }
+
+                open.push(name);
+                self.expand_into(value, out, open, options, expanded)?;
+                open.pop();
+            } else {
+                let range = offset + 1..offset + end;
+                let error = EscapeError::UnrecognizedEntity(range, name.to_owned());
+                return Err(XmlError::from(error).into());
+            }
+
+            rest = &reference[end + 1..];
+        }
+
+        out.push_str(rest);
+        Ok(())
+    }
+}
+
+/** Split a markup declaration following ```<!``` into tokens, returning them and the input after the closing ```>```. */
+fn declaration(mut rest: &str) -> Result<(Vec<Token<'_>>, &str), DocTypeError> {
+    let mut tokens = Vec::new();
+
+    loop {
+        rest = rest.trim_start_matches(is_space);
+        let Some(first) = rest.chars().next() else {
+            return Err(DocTypeError::MalformedDeclaration);
+        };
+
+        rest = match first {
+            '>' => return Ok((tokens, &rest[1..])),
+            '"' | '\'' => {
+                let (literal, after) = rest[1..]
+                    .split_once(first)
+                    .ok_or(DocTypeError::MalformedDeclaration)?;
+                tokens.push(Token::Literal(literal));
+                after
+            }
+            '(' => {
+                let mut depth = 0usize;
+                let end = rest
+                    .find(|c| {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            _ => (),
+                        }
+                        depth == 0
+                    })
+                    .ok_or(DocTypeError::MalformedDeclaration)?;
+                tokens.push(Token::Group);
+                &rest[end + 1..]
+            }
+            _ => {
+                let end = rest
+                    .find(|c| is_space(c) || matches!(c, '>' | '"' | '\'' | '('))
+                    .unwrap_or(rest.len());
+                tokens.push(Token::Word(&rest[..end]));
+                &rest[end..]
+            }
+        };
+    }
+}
+
+/** Get the input after the given terminator. */
+fn skip_past<'a>(rest: &'a str, terminator: &str) -> Result<&'a str, DocTypeError> {
+    let (_, after) = rest
+        .split_once(terminator)
+        .ok_or(DocTypeError::MalformedDeclaration)?;
+    Ok(after)
+}
+
+/** Decode the character references in an entity value, keeping entity references for later expansion. */
+fn decode_char_refs(value: &str) -> Result<String, DocTypeError> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("&#") {
+        out.push_str(&rest[..start]);
+        let reference = &rest[start..];
+        let end = reference
+            .find(';')
+            .ok_or(DocTypeError::MalformedDeclaration)?;
+        let decoded =
+            unescape(&reference[..=end]).map_err(|_| DocTypeError::MalformedDeclaration)?;
+        out.push_str(&decoded);
+        rest = &reference[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
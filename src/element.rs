@@ -1,17 +1,55 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    fmt::{Debug, Display, Write},
+    hash::{Hash, Hasher},
+    mem,
+};
 
-use crate::{stringify, Item};
+use crate::{
+    namespace::split_name, serialize::Serializer, span::ElementSpans, AttributeSpan, Attributes,
+    Descendants, DescendantsAtDepth, DescendantsAtDepthMut, DescendantsBreadthFirst,
+    DescendantsMut, Item, NodePath, SerializeOptions, Span,
+};
 
-/** Element ```<tag attr="value">...</tag>``` or ```<tag attr="value" />```. */
+/** Element ```<tag attr="value">...</tag>``` or ```<tag attr="value" />```.
+
+Elements are compared and hashed by their name, attributes, children and namespace.
+The order of attributes and where the element was found in the input do not matter.
+
+Elements may be nested arbitrarily deep. Nothing in this crate recurses into children,
+whether dropping, cloning, comparing, hashing, formatting, writing or visiting elements,
+so deep nesting cannot overflow the call stack.
+
+Elements implement [`Drop`], so their fields cannot be moved out of them.
+Use [`into_children`](Self::into_children) or [`into_parts`](Self::into_parts) instead.
+```rust
+# use larix::*;
+let items = parse_with(r#"<a x="1" y="2"><b /></a>"#, &ParseOptions::new().spans(true))?;
+let Item::Element(element) = &items[0] else {
+    panic!();
+};
+
+let mut copy = Element::new(String::from("a"));
+copy.attributes.insert(String::from("y"), String::from("2"));
+copy.attributes.insert(String::from("x"), String::from("1"));
+let mut child = Element::new(String::from("b"));
+child.self_closing = true;
+copy.children.push(Item::Element(child));
+assert_eq!(element, &copy);
+# Ok::<(), Error>(())
+```*/
 pub struct Element {
     /** Tag name of the element. */
     pub name: String,
     /** Items between the start and end tags of the element. */
     pub children: Vec<Item>,
-    /** Attributes of the element. */
-    pub attributes: HashMap<String, String>,
+    /** Attributes of the element, in order. */
+    pub attributes: Attributes,
     /** Whether to self-close if childless. */
     pub self_closing: bool,
+    /** Namespace of the element, if resolved. */
+    pub(crate) namespace: Option<String>,
+    /** Where the element was found in the input, if recorded. */
+    pub(crate) spans: Option<Box<ElementSpans>>,
 }
 
 impl Element {
@@ -19,12 +57,44 @@ impl Element {
         Element {
             name,
             children: Vec::new(),
-            attributes: HashMap::new(),
+            attributes: Attributes::new(),
             self_closing: false,
+            namespace: None,
+            spans: None,
         }
     }
 
+    /** Take the children out of the element, dropping the rest of it. */
+    pub fn into_children(mut self) -> Vec<Item> {
+        mem::take(&mut self.children)
+    }
+
+    /** Take the name, attributes and children out of the element, dropping the rest of it.
+    ```rust
+    # use larix::*;
+    let items = parse(r#"<a x="1"><b /></a>"#)?;
+    let Some(Item::Element(element)) = items.into_iter().next() else {
+        panic!();
+    };
+
+    let (name, attributes, children) = element.into_parts();
+    assert_eq!(name, "a");
+    assert_eq!(attributes.get("x").map(String::as_str), Some("1"));
+    assert_eq!(children.len(), 1);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn into_parts(mut self) -> (String, Attributes, Vec<Item>) {
+        (
+            mem::take(&mut self.name),
+            mem::take(&mut self.attributes),
+            mem::take(&mut self.children),
+        )
+    }
+
     /** Get all descendants matching the predicate.
+
+    The matching children come first, followed by the matches within each child element in turn.
+    To stop at the first match, or to get the matches in document order, filter [`descendants`](Self::descendants) instead.
     ```rust
     // Example of finding all elements with tag name "a":
     let xml = "<element><a></a><b><a></a></b><c>text</c></element>";
@@ -45,17 +115,57 @@ impl Element {
     # Ok::<(), Error>(())
     ```*/
     pub fn find_descendants(&self, predicate: &impl Fn(&Item) -> bool) -> Vec<&Item> {
-        let mut result: Vec<&Item> = self
-            .children
-            .iter()
-            .filter(|item| predicate(item))
-            .collect();
+        let mut result = Vec::new();
 
-        for child in &self.children {
-            let Item::Element(element) = child else {
-                continue;
-            };
-            result.append(&mut element.find_descendants(predicate));
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            result.extend(element.children.iter().filter(|item| predicate(item)));
+            stack.extend(element.children.iter().rev().filter_map(|item| match item {
+                Item::Element(child) => Some(child),
+                _ => None,
+            }));
+        }
+
+        result
+    }
+
+    /** Get all descendants matching the predicate for modification, in document order.
+
+    The items within a match are not searched, as the match already gives access to them.
+    ```rust
+    # use larix::*;
+    let mut items = parse(r#"<list><price>3</price><item><price>5</price></item></list>"#)?;
+    let Item::Element(list) = &mut items[0] else {
+        panic!();
+    };
+
+    let prices = list.find_descendants_mut(&|item| matches!(item, Item::Element(el) if el.name == "price"));
+    for price in prices {
+        let Item::Element(price) = price else {
+            panic!();
+        };
+        price.attributes.insert(String::from("currency"), String::from("EUR"));
+    }
+
+    assert_eq!(
+        list.to_string(),
+        r#"<list><price currency="EUR">3</price><item><price currency="EUR">5</price></item></list>"#
+    );
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn find_descendants_mut(&mut self, predicate: &impl Fn(&Item) -> bool) -> Vec<&mut Item> {
+        let mut result = Vec::new();
+
+        let mut stack = vec![self.children.iter_mut()];
+        while let Some(children) = stack.last_mut() {
+            match children.next() {
+                Some(item) if predicate(item) => result.push(item),
+                Some(Item::Element(element)) => stack.push(element.children.iter_mut()),
+                Some(_) => (),
+                None => {
+                    stack.pop();
+                }
+            }
         }
 
         result
@@ -66,36 +176,27 @@ impl Element {
     <element>Hello<child>World</child></element>
     ```
     The above would result in "HelloWorld".*/
-    pub fn get_text_content(self: &Self) -> String {
+    pub fn get_text_content(&self) -> String {
         let mut content = String::new();
 
-        for child in &self.children {
-            match child {
-                Item::Text(text) => {
-                    content.push_str(&text);
-                }
-                Item::Element(element) => {
-                    content.push_str(&element.get_text_content());
+        let mut stack = vec![self.children.iter()];
+        while let Some(children) = stack.last_mut() {
+            match children.next() {
+                Some(Item::Text(text)) => content.push_str(text),
+                Some(Item::Element(element)) => stack.push(element.children.iter()),
+                Some(_) => (),
+                None => {
+                    stack.pop();
                 }
-                _ => (),
             }
         }
 
         content
     }
 
-    /** Get all children which are elements. */
-    pub fn get_child_elements(self: &Self) -> Vec<&Element> {
-        let mut elements = Vec::new();
-
-        for child in &self.children {
-            let Item::Element(element) = child else {
-                continue;
-            };
-            elements.push(element)
-        }
-
-        elements
+    /** Get all children which are elements. See [`child_elements`](Self::child_elements) for an iterator. */
+    pub fn get_child_elements(&self) -> Vec<&Element> {
+        self.child_elements().collect()
     }
 
     /** Get all items at a certain depth within the element.
@@ -107,60 +208,576 @@ impl Element {
             </item>
         </item>
     </element>
-    ```*/
-    pub fn get_decendants_at_depth(self: &Self, depth: u8) -> Vec<&Item> {
+    ```
+    Returns `None` if the depth is zero, as the element itself is not an item.
+    See [`descendants_at_depth`](Self::descendants_at_depth) for an iterator. */
+    pub fn get_decendants_at_depth(&self, depth: u8) -> Option<Vec<&Item>> {
         if depth == 0 {
-            panic!("Depth cannot be zero.");
+            return None;
         }
-        if depth == 1 {
-            return self.children.iter().collect();
+        Some(self.descendants_at_depth(depth.into()).collect())
+    }
+
+    /** Iterate over the children which are elements. */
+    pub fn child_elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|item| match item {
+            Item::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /** Iterate over the children which are elements, allowing modification. */
+    pub fn child_elements_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        self.children.iter_mut().filter_map(|item| match item {
+            Item::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /** Iterate over all descendants in depth-first order, i.e. the order they appear in the document.
+
+    Items are only visited as far as the iterator is advanced.
+    ```rust
+    # use larix::*;
+    let items = parse("<a><b><c /></b>text<d /></a>")?;
+    let Item::Element(a) = &items[0] else {
+        panic!();
+    };
+
+    let names: Vec<&str> = a
+        .descendants()
+        .filter_map(|item| match item {
+            Item::Element(element) => Some(element.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["b", "c", "d"]);
+
+    let text = a.descendants().find(|item| matches!(item, Item::Text(_)));
+    assert_eq!(text.map(Item::to_string).as_deref(), Some("text"));
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn descendants(&self) -> Descendants<'_> {
+        Descendants::new(&self.children)
+    }
+
+    /** Iterate over all descendants in breadth-first order, i.e. all children first, then all grandchildren, and so on.
+
+    Items are only visited as far as the iterator is advanced.
+    ```rust
+    # use larix::*;
+    let items = parse("<a><b><c /></b><d /></a>")?;
+    let Item::Element(a) = &items[0] else {
+        panic!();
+    };
+
+    let names: Vec<String> = a.descendants_breadth_first().map(Item::to_string).collect();
+    assert_eq!(names, ["<b><c /></b>", "<d />", "<c />"]);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn descendants_breadth_first(&self) -> DescendantsBreadthFirst<'_> {
+        DescendantsBreadthFirst::new(&self.children)
+    }
+
+    /** Iterate over the items at a certain depth within the element, in document order.
+
+    Children are at depth 1, grandchildren at depth 2, and so on. Nothing is at depth 0.
+    Elements deeper than the given depth are not visited. */
+    pub fn descendants_at_depth(&self, depth: usize) -> DescendantsAtDepth<'_> {
+        DescendantsAtDepth::new(&self.children, depth)
+    }
+
+    /** Iterate over all descendants which are not elements in document order, allowing modification.
+
+    Elements are passed through rather than returned, as access to an element would include the items within it.
+    To modify elements, use [`find_descendants_mut`](Self::find_descendants_mut) or [`descendants_at_depth_mut`](Self::descendants_at_depth_mut).
+    ```rust
+    # use larix::*;
+    let mut items = parse("<a>one<b>two</b><!-- three --></a>")?;
+    let Item::Element(a) = &mut items[0] else {
+        panic!();
+    };
+
+    for item in a.descendants_mut() {
+        if let Item::Text(text) = item {
+            *text = text.to_uppercase();
+        }
+    }
+
+    assert_eq!(a.to_string(), "<a>ONE<b>TWO</b><!-- three --></a>");
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn descendants_mut(&mut self) -> DescendantsMut<'_> {
+        DescendantsMut::new(&mut self.children)
+    }
+
+    /** Iterate over the items at a certain depth within the element, in document order, allowing modification.
+
+    See [`descendants_at_depth`](Self::descendants_at_depth). */
+    pub fn descendants_at_depth_mut(&mut self, depth: usize) -> DescendantsAtDepthMut<'_> {
+        DescendantsAtDepthMut::new(&mut self.children, depth)
+    }
+
+    /** Get the item at the given path. Returns `None` if the path is empty or leads nowhere. */
+    pub fn get_path(&self, path: &NodePath) -> Option<&Item> {
+        let (index, parents) = path.0.split_last()?;
+        self.children_at(parents)?.get(*index)
+    }
+
+    /** Get the item at the given path for modification. Returns `None` if the path is empty or leads nowhere. */
+    pub fn get_path_mut(&mut self, path: &NodePath) -> Option<&mut Item> {
+        let (index, parents) = path.0.split_last()?;
+        self.children_at_mut(parents)?.get_mut(*index)
+    }
+
+    /** Remove the item at the given path, returning it. Returns `None` if the path is empty or leads nowhere.
+
+    The following siblings of the item move up by one, so paths to them and within them change. */
+    pub fn remove_at_path(&mut self, path: &NodePath) -> Option<Item> {
+        let (index, parents) = path.0.split_last()?;
+        let children = self.children_at_mut(parents)?;
+        if *index < children.len() {
+            Some(children.remove(*index))
+        } else {
+            None
+        }
+    }
+
+    /** Insert an item so that it is found at the given path.
+
+    The path has to lead to an element, and may end just after its last child.
+    Otherwise the item is given back. The following siblings move down by one. */
+    pub fn insert_at_path(&mut self, path: &NodePath, item: Item) -> Result<(), Item> {
+        let Some((index, parents)) = path.0.split_last() else {
+            return Err(item);
+        };
+        match self.children_at_mut(parents) {
+            Some(children) if *index <= children.len() => {
+                children.insert(*index, item);
+                Ok(())
+            }
+            _ => Err(item),
+        }
+    }
+
+    /** Get the children of the element found by descending into the children at the given indices. */
+    fn children_at(&self, indices: &[usize]) -> Option<&Vec<Item>> {
+        let mut children = &self.children;
+        for index in indices {
+            children = match children.get(*index)? {
+                Item::Element(element) => &element.children,
+                _ => return None,
+            };
         }
+        Some(children)
+    }
+
+    /** Get the children of the element found by descending into the children at the given indices, for modification. */
+    fn children_at_mut(&mut self, indices: &[usize]) -> Option<&mut Vec<Item>> {
+        let mut children = &mut self.children;
+        for index in indices {
+            children = match children.get_mut(*index)? {
+                Item::Element(element) => &mut element.children,
+                _ => return None,
+            };
+        }
+        Some(children)
+    }
+
+    /** Get the prefix of the tag name, e.g. ```svg``` for ```svg:rect```. */
+    pub fn prefix(&self) -> Option<&str> {
+        split_name(&self.name).0
+    }
 
-        self.children
+    /** Get the tag name without its prefix, e.g. ```rect``` for ```svg:rect```. */
+    pub fn local_name(&self) -> &str {
+        split_name(&self.name).1
+    }
+
+    /** Get the namespace URI of the element.
+
+    Namespaces are only resolved if enabled through [`ParseOptions::namespaces`](crate::ParseOptions::namespaces).
+    They reflect the declarations in scope where the element was parsed. */
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /** Get the value of the attribute with the given namespace and local name. */
+    pub fn attribute_ns(&self, namespace: &str, local_name: &str) -> Option<&String> {
+        self.attributes.get_ns(namespace, local_name)
+    }
+
+    /** Get all descendant elements with the given namespace and local name.
+    ```rust
+    let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:a="http://www.w3.org/2005/Atom">
+        <entry /><a:entry /><entry xmlns="urn:other" />
+    </feed>"#;
+
+    # use larix::*;
+    let items = parse_with(xml, &ParseOptions::new().namespaces(true))?;
+    let Item::Element(feed) = &items[0] else {
+        panic!();
+    };
+
+    let entries = feed.find_elements_ns("http://www.w3.org/2005/Atom", "entry");
+
+    assert_eq!(entries.len(), 2);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn find_elements_ns(&self, namespace: &str, local_name: &str) -> Vec<&Element> {
+        self.find_descendants(&|item| {
+            let Item::Element(el) = item else {
+                return false;
+            };
+            el.namespace() == Some(namespace) && el.local_name() == local_name
+        })
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Element(el) => Some(el),
+            _ => None,
+        })
+        .collect()
+    }
+
+    /** Get the outermost descendant elements with the given namespace and local name for modification.
+
+    See [`find_elements_ns`](Self::find_elements_ns) and [`find_descendants_mut`](Self::find_descendants_mut). */
+    pub fn find_elements_ns_mut(&mut self, namespace: &str, local_name: &str) -> Vec<&mut Element> {
+        self.find_descendants_mut(&|item| {
+            let Item::Element(el) = item else {
+                return false;
+            };
+            el.namespace() == Some(namespace) && el.local_name() == local_name
+        })
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Element(el) => Some(el),
+            _ => None,
+        })
+        .collect()
+    }
+
+    /** Get the span of the whole element, from the start of its start tag to the end of its end tag.
+
+    Spans are only recorded if enabled through [`ParseOptions::spans`](crate::ParseOptions::spans). */
+    pub fn span(&self) -> Option<Span> {
+        let spans = self.spans.as_ref()?;
+        Some(Span {
+            start: spans.start_tag.start,
+            end: spans.end_tag.unwrap_or(spans.start_tag).end,
+        })
+    }
+
+    /** Get the span of the start tag, or of the whole tag if the element is self-closing. */
+    pub fn start_tag_span(&self) -> Option<Span> {
+        Some(self.spans.as_ref()?.start_tag)
+    }
+
+    /** Get the span of the end tag. `None` if the element is self-closing. */
+    pub fn end_tag_span(&self) -> Option<Span> {
+        self.spans.as_ref()?.end_tag
+    }
+
+    /** Get the span of everything between the start and end tags. `None` if the element is self-closing. */
+    pub fn content_span(&self) -> Option<Span> {
+        let spans = self.spans.as_ref()?;
+        Some(Span {
+            start: spans.start_tag.end,
+            end: spans.end_tag?.start,
+        })
+    }
+
+    /** Get the spans of the name and value of an attribute. */
+    pub fn attribute_span(&self, name: &str) -> Option<AttributeSpan> {
+        self.spans
+            .as_ref()?
+            .attributes
             .iter()
-            .filter_map(|item| {
-                let Item::Element(element) = item else {
-                    return None;
-                };
-                Some(element.get_decendants_at_depth(depth - 1))
-            })
-            .reduce(|mut acc, mut curr| {
-                acc.append(&mut curr);
-                acc
-            })
-            .unwrap_or(Vec::new())
+            .find(|(attr, _)| attr == name)
+            .map(|(_, span)| *span)
+    }
+
+    /** Get the span of the child at the given index.
+
+    This also covers children which are not elements, such as text or comments.
+    The spans are recorded when parsing and are not updated when the children are modified,
+    so after inserting or removing children, the index no longer matches the child the span belongs to. */
+    pub fn child_span(&self, index: usize) -> Option<Span> {
+        self.spans.as_ref()?.children.get(index).copied()
     }
 }
 
-impl Display for Element {
+impl Drop for Element {
+    /** Drop the descendants one after another. */
+    fn drop(&mut self) {
+        let mut stack = mem::take(&mut self.children);
+        while let Some(item) = stack.pop() {
+            if let Item::Element(mut element) = item {
+                stack.append(&mut element.children);
+            }
+        }
+    }
+}
+
+impl Element {
+    /** Copy the element without its children. */
+    fn clone_tag(&self) -> Self {
+        Element {
+            name: self.name.clone(),
+            children: Vec::with_capacity(self.children.len()),
+            attributes: self.attributes.clone(),
+            self_closing: self.self_closing,
+            namespace: self.namespace.clone(),
+            spans: self.spans.clone(),
+        }
+    }
+
+    /** Whether the elements are equal, disregarding their children. */
+    fn eq_tag(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.attributes == other.attributes
+            && self.self_closing == other.self_closing
+            && self.namespace == other.namespace
+    }
+}
+
+impl Clone for Element {
+    fn clone(&self) -> Self {
+        // Elements whose children are still being copied, with the iterators over the remaining originals.
+        let mut stack = Vec::new();
+        let mut children = self.children.iter();
+        let mut copy = self.clone_tag();
+
+        loop {
+            match children.next() {
+                Some(Item::Element(element)) => {
+                    stack.push((children, copy));
+                    children = element.children.iter();
+                    copy = element.clone_tag();
+                }
+                Some(item) => copy.children.push(item.clone()),
+                None => match stack.pop() {
+                    Some((siblings, mut parent)) => {
+                        parent.children.push(Item::Element(copy));
+                        children = siblings;
+                        copy = parent;
+                    }
+                    None => return copy,
+                },
+            }
+        }
+    }
+}
+
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if !a.eq_tag(b) || a.children.len() != b.children.len() {
+                return false;
+            }
+            for pair in a.children.iter().zip(&b.children) {
+                match pair {
+                    (Item::Element(a), Item::Element(b)) => stack.push((a, b)),
+                    (a, b) if a != b => return false,
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+}
+
+impl Eq for Element {}
+
+impl Hash for Element {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            element.name.hash(state);
+            element.attributes.hash(state);
+            element.self_closing.hash(state);
+            element.namespace.hash(state);
+            element.children.len().hash(state);
+            for child in &element.children {
+                mem::discriminant(child).hash(state);
+                match child {
+                    Item::Element(child) => stack.push(child),
+                    item => item.hash(state),
+                }
+            }
+        }
+    }
+}
+
+impl Debug for Element {
+    /** Write the name, attributes and children like a derived implementation would. The namespace is included if resolved. */
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str = if self.self_closing {
-            let mut str = get_start_tag(self);
-            str.insert_str(str.len() - 1, " /");
-            str
-        } else {
-            format!(
-                "{}{}{}",
-                get_start_tag(self),
-                stringify(&self.children),
-                get_end_tag(self)
-            )
+        let pretty = f.alternate();
+        let mut out = DebugWriter {
+            f,
+            pretty,
+            depth: 0,
+            on_newline: false,
         };
 
-        write!(f, "{str}")
+        // Each open element, with its remaining children and whether it is wrapped in an item.
+        out.open(self)?;
+        let mut stack = vec![(self, self.children.iter(), false)];
+        while let Some((element, children, _)) = stack.last_mut() {
+            let first = children.len() == element.children.len();
+            match children.next() {
+                Some(item) => {
+                    if !first && !pretty {
+                        out.write_str(", ")?;
+                    }
+                    match item {
+                        Item::Element(child) => {
+                            out.wrap()?;
+                            out.open(child)?;
+                            stack.push((child, child.children.iter(), true));
+                        }
+                        item => {
+                            out.value(item)?;
+                            out.separate()?;
+                        }
+                    }
+                }
+                None => {
+                    let self_closing = element.self_closing;
+                    let empty = element.children.is_empty();
+                    let wrapped = matches!(stack.pop(), Some((_, _, true)));
+                    out.close(self_closing, empty)?;
+                    if wrapped {
+                        out.unwrap()?;
+                        out.separate()?;
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
 
-fn get_start_tag(element: &Element) -> String {
-    let mut attributes = String::new();
+/** Writes the debug output of elements, indenting the lines of nested values like [`Formatter::debug_struct`](std::fmt::Formatter::debug_struct) does. */
+struct DebugWriter<'a, 'f> {
+    f: &'a mut std::fmt::Formatter<'f>,
+    /** Whether to write each field and item on its own line, as requested by ```{:#?}```. */
+    pretty: bool,
+    depth: usize,
+    on_newline: bool,
+}
+
+impl DebugWriter<'_, '_> {
+    /** Write an element up to the opening bracket of its children. */
+    fn open(&mut self, element: &Element) -> std::fmt::Result {
+        self.write_str("Element {")?;
+        self.begin()?;
+        self.field("name", &element.name)?;
+        if let Some(namespace) = &element.namespace {
+            self.field("namespace", namespace)?;
+        }
+        self.field("attributes", &element.attributes)?;
+        self.write_str("children: [")?;
+        if !element.children.is_empty() {
+            self.begin_list()?;
+        }
+        Ok(())
+    }
+
+    /** Write the rest of an element after its children. */
+    fn close(&mut self, self_closing: bool, empty: bool) -> std::fmt::Result {
+        if !empty && self.pretty {
+            self.depth = self.depth.saturating_sub(1);
+        }
+        self.write_str("]")?;
+        self.write_str(if self.pretty { ",\n" } else { ", " })?;
+        self.write_str("self_closing: ")?;
+        self.value(&self_closing)?;
+        if self.pretty {
+            self.write_str(",\n")?;
+            self.depth = self.depth.saturating_sub(1);
+            self.write_str("}")
+        } else {
+            self.write_str(" }")
+        }
+    }
+
+    /** Write the start of an element wrapped in an item. */
+    fn wrap(&mut self) -> std::fmt::Result {
+        self.write_str("Element(")?;
+        self.begin_list()
+    }
+
+    /** Write the end of an element wrapped in an item. */
+    fn unwrap(&mut self) -> std::fmt::Result {
+        if self.pretty {
+            self.write_str(",\n")?;
+            self.depth = self.depth.saturating_sub(1);
+        }
+        self.write_str(")")
+    }
+
+    fn field(&mut self, name: &str, value: &dyn Debug) -> std::fmt::Result {
+        self.write_str(name)?;
+        self.write_str(": ")?;
+        self.value(value)?;
+        self.write_str(if self.pretty { ",\n" } else { ", " })
+    }
+
+    fn value(&mut self, value: &dyn Debug) -> std::fmt::Result {
+        if self.pretty {
+            write!(self, "{value:#?}")
+        } else {
+            write!(self, "{value:?}")
+        }
+    }
+
+    /** Start the fields of a struct. */
+    fn begin(&mut self) -> std::fmt::Result {
+        if self.pretty {
+            self.depth += 1;
+            self.write_str("\n")
+        } else {
+            self.write_str(" ")
+        }
+    }
+
+    /** Start the items of a list or tuple. */
+    fn begin_list(&mut self) -> std::fmt::Result {
+        if self.pretty {
+            self.depth += 1;
+            self.write_str("\n")?;
+        }
+        Ok(())
+    }
 
-    for attr in &element.attributes {
-        attributes.push_str(&format!(r#" {}="{}""#, attr.0, attr.1));
+    /** End an item of a list. */
+    fn separate(&mut self) -> std::fmt::Result {
+        if self.pretty {
+            self.write_str(",\n")?;
+        }
+        Ok(())
     }
+}
 
-    format!("<{}{}>", element.name, attributes)
+impl std::fmt::Write for DebugWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.on_newline {
+                for _ in 0..self.depth {
+                    self.f.write_str("    ")?;
+                }
+            }
+            self.on_newline = line.ends_with('\n');
+            self.f.write_str(line)?;
+        }
+        Ok(())
+    }
 }
 
-fn get_end_tag(element: &Element) -> String {
-    format!("</{}>", element.name)
+impl Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Serializer::new(f, &SerializeOptions::new()).element(self, 0)
+    }
 }
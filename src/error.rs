@@ -0,0 +1,366 @@
+use std::{fmt::Display, io, sync::Arc};
+
+use quick_xml::events::attributes::AttrError;
+
+/** Error returned when XML could not be parsed. */
+#[derive(Clone, Debug)]
+pub enum Error {
+    /** The input is not well-formed XML. */
+    Xml {
+        /** Error reported by the underlying parser. */
+        cause: quick_xml::Error,
+        /** Where in the input the error occurred. */
+        position: Position,
+    },
+    /** An attribute is malformed, e.g. it is duplicated or its value is not quoted. */
+    Attribute {
+        /** Name of the element the attribute belongs to. */
+        element: String,
+        /** Name of the malformed attribute. */
+        attribute: String,
+        /** Error reported by the underlying parser. */
+        cause: AttrError,
+        /** Where in the input the error occurred. */
+        position: Position,
+    },
+    /** The input contains a DOCTYPE, which was rejected by the parse options. */
+    DocType {
+        /** Where in the input the DOCTYPE was found. */
+        position: Position,
+    },
+    /** The input contains a DOCTYPE which could not be parsed. */
+    MalformedDocType {
+        /** What is wrong with the DOCTYPE. */
+        reason: DocTypeError,
+        /** Where in the input the DOCTYPE was found. */
+        position: Position,
+    },
+    /** The input is not a document with a single root element. */
+    Document {
+        /** How the structure of the document is wrong. */
+        reason: DocumentError,
+        /** Where in the input the problem was found. */
+        position: Position,
+    },
+    /** The input contains a processing instruction which cannot be represented. */
+    ProcessingInstruction {
+        /** What is wrong with the processing instruction. */
+        reason: PIError,
+        /** Where in the input the processing instruction was found. */
+        position: Position,
+    },
+    /** The input exceeds a limit set in the parse options. */
+    Limit {
+        /** Which limit was exceeded. */
+        limit: Limit,
+        /** Where in the input the limit was exceeded. */
+        position: Position,
+    },
+    /** The input could not be read. */
+    Io(Arc<io::Error>),
+}
+
+impl Error {
+    /** Get the location in the input at which the error occurred, if known. */
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            Self::Xml { position, .. }
+            | Self::Attribute { position, .. }
+            | Self::DocType { position }
+            | Self::MalformedDocType { position, .. }
+            | Self::Document { position, .. }
+            | Self::ProcessingInstruction { position, .. }
+            | Self::Limit { position, .. } => Some(position),
+            Self::Io(_) => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Xml { cause, position } => write!(f, "{cause} at {position}"),
+            Self::Attribute {
+                element,
+                attribute,
+                cause,
+                position,
+            } => write!(
+                f,
+                "malformed attribute `{attribute}` of element `{element}`: {cause} at {position}"
+            ),
+            Self::DocType { position } => write!(f, "DOCTYPE is not allowed at {position}"),
+            Self::MalformedDocType { reason, position } => write!(f, "{reason} at {position}"),
+            Self::Document { reason, position } => write!(f, "{reason} at {position}"),
+            Self::ProcessingInstruction { reason, position } => {
+                write!(f, "{reason} at {position}")
+            }
+            Self::Limit { limit, position } => write!(f, "{limit} at {position}"),
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Xml { cause, .. } => Some(cause),
+            Self::Attribute { cause, .. } => Some(cause),
+            Self::MalformedDocType { reason, .. } => Some(reason),
+            Self::ProcessingInstruction { reason, .. } => Some(reason),
+            Self::DocType { .. } | Self::Document { .. } | Self::Limit { .. } => None,
+            Self::Io(err) => Some(err.as_ref()),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(Arc::new(err))
+    }
+}
+
+/** Reason why a DOCTYPE could not be parsed. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocTypeError {
+    /** The DOCTYPE does not start with the name of the root element. */
+    MissingName,
+    /** ```SYSTEM``` or ```PUBLIC``` is not followed by the right number of quoted literals. */
+    MalformedExternalId,
+    /** A literal of the external ID contains both ```"``` and ```'```, so it cannot be quoted. */
+    InvalidLiteral,
+    /** The internal subset has no closing ```]```. */
+    UnclosedInternalSubset,
+    /** The DOCTYPE contains something after its name, external ID and internal subset. */
+    TrailingContent,
+    /** The internal subset contains something other than well-formed markup declarations. */
+    MalformedDeclaration,
+    /** An entity refers to itself, directly or through other entities. */
+    RecursiveEntity,
+}
+
+impl Display for DocTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingName => write!(f, "DOCTYPE has no name"),
+            Self::MalformedExternalId => write!(f, "malformed external ID in DOCTYPE"),
+            Self::InvalidLiteral => write!(f, "literal in DOCTYPE contains both kinds of quotes"),
+            Self::UnclosedInternalSubset => write!(f, "unclosed internal subset in DOCTYPE"),
+            Self::TrailingContent => write!(f, "unexpected content in DOCTYPE"),
+            Self::MalformedDeclaration => write!(f, "malformed declaration in internal subset"),
+            Self::RecursiveEntity => write!(f, "entity refers to itself"),
+        }
+    }
+}
+
+impl std::error::Error for DocTypeError {}
+
+/** Limit set in the parse options. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /** See [`ParseOptions::max_entity_depth`](crate::ParseOptions::max_entity_depth). */
+    EntityDepth,
+    /** See [`ParseOptions::max_entity_expansion`](crate::ParseOptions::max_entity_expansion). */
+    EntityExpansion,
+    /** See [`ParseOptions::max_depth`](crate::ParseOptions::max_depth). */
+    Depth,
+    /** See [`ParseOptions::max_input_size`](crate::ParseOptions::max_input_size). */
+    InputSize,
+    /** See [`ParseOptions::max_attributes`](crate::ParseOptions::max_attributes). */
+    Attributes,
+    /** See [`ParseOptions::max_attribute_length`](crate::ParseOptions::max_attribute_length). */
+    AttributeLength,
+    /** See [`ParseOptions::max_text_length`](crate::ParseOptions::max_text_length). */
+    TextLength,
+    /** See [`ParseOptions::max_items`](crate::ParseOptions::max_items). */
+    Items,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EntityDepth => write!(f, "entity references are nested too deeply"),
+            Self::EntityExpansion => write!(f, "entity references expand to too much text"),
+            Self::Depth => write!(f, "elements are nested too deeply"),
+            Self::InputSize => write!(f, "input is too large"),
+            Self::Attributes => write!(f, "element has too many attributes"),
+            Self::AttributeLength => write!(f, "attribute value is too long"),
+            Self::TextLength => write!(f, "text is too long"),
+            Self::Items => write!(f, "input contains too many items"),
+        }
+    }
+}
+
+/** Reason why the input is not a well-formed document. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentError {
+    /** The input contains no element. */
+    MissingRoot,
+    /** The input contains another element after the root element. */
+    MultipleRoots,
+    /** The input contains text or CDATA outside of the root element. */
+    TextOutsideRoot,
+    /** The input contains an XML declaration which is not at its very start. */
+    MisplacedDecl,
+    /** The input contains a DOCTYPE after the root element. */
+    MisplacedDocType,
+    /** The input contains more than one DOCTYPE. */
+    MultipleDocTypes,
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingRoot => write!(f, "document has no root element"),
+            Self::MultipleRoots => write!(f, "document has more than one root element"),
+            Self::TextOutsideRoot => write!(f, "text is not allowed outside of the root element"),
+            Self::MisplacedDecl => write!(
+                f,
+                "XML declaration is only allowed at the start of the document"
+            ),
+            Self::MisplacedDocType => write!(f, "DOCTYPE is not allowed after the root element"),
+            Self::MultipleDocTypes => write!(f, "document has more than one DOCTYPE"),
+        }
+    }
+}
+
+/** Reason why an XML declaration is invalid. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeclError {
+    /** The declaration is not of the form ```xml version="..." encoding="..." standalone="..."```. */
+    Malformed,
+    /** The declaration does not start with a version. */
+    MissingVersion,
+    /** The version is not of the form ```1.x```. */
+    InvalidVersion(String),
+    /** The encoding is not a valid encoding name. */
+    InvalidEncoding(String),
+    /** The standalone value is neither ```yes``` nor ```no```. */
+    InvalidStandalone(String),
+}
+
+impl Display for DeclError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed XML declaration"),
+            Self::MissingVersion => write!(f, "XML declaration has no version"),
+            Self::InvalidVersion(version) => write!(f, "invalid XML version `{version}`"),
+            Self::InvalidEncoding(encoding) => write!(f, "invalid encoding name `{encoding}`"),
+            Self::InvalidStandalone(standalone) => {
+                write!(f, "invalid standalone value `{standalone}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeclError {}
+
+/** Reason why a processing instruction is invalid. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PIError {
+    /** The target is named ```xml``` in any case, which is reserved. */
+    ReservedTarget(String),
+    /** The target is empty or contains whitespace. */
+    InvalidTarget(String),
+    /** The data contains ```?>```. */
+    InvalidData,
+}
+
+impl Display for PIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReservedTarget(target) => {
+                write!(f, "processing instruction target `{target}` is reserved")
+            }
+            Self::InvalidTarget(target) => {
+                write!(f, "invalid processing instruction target `{target}`")
+            }
+            Self::InvalidData => write!(f, "processing instruction data must not contain `?>`"),
+        }
+    }
+}
+
+impl std::error::Error for PIError {}
+
+/** Location within the input. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    /** Byte offset from the start of the input. */
+    pub offset: u64,
+    /** Line number, starting at 1. */
+    pub line: u64,
+    /** Column number in bytes, starting at 1. */
+    pub column: u64,
+    /** Path of the innermost open element, e.g. ```/config/servers/server[3]```.
+    Problems within a start tag, such as malformed attributes, are located at the element being started.
+
+    Elements are numbered among their siblings of the same name, starting at 1.
+    The number is omitted for the first of them. The path is ```/``` at the top level. */
+    pub path: String,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {} ({})",
+            self.line, self.column, self.path
+        )
+    }
+}
+
+/** Reason for an error, before its position is known. */
+pub(crate) enum Cause {
+    Xml(quick_xml::Error),
+    Attribute {
+        element: String,
+        attribute: String,
+        cause: AttrError,
+        /** Byte offset of the malformed attribute. */
+        offset: u64,
+    },
+    DocType,
+    MalformedDocType(DocTypeError),
+    Document(DocumentError),
+    PI(PIError),
+    Limit(Limit),
+}
+
+impl Cause {
+    /** Get the byte offset at which the error occurred, if more precise than the start of the event. */
+    pub(crate) fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Attribute { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn at(self, position: Position) -> Error {
+        match self {
+            Self::Xml(quick_xml::Error::Io(err)) => Error::Io(err),
+            Self::Xml(cause) => Error::Xml { cause, position },
+            Self::Attribute {
+                element,
+                attribute,
+                cause,
+                ..
+            } => Error::Attribute {
+                element,
+                attribute,
+                cause,
+                position,
+            },
+            Self::DocType => Error::DocType { position },
+            Self::MalformedDocType(reason) => Error::MalformedDocType { reason, position },
+            Self::Document(reason) => Error::Document { reason, position },
+            Self::PI(reason) => Error::ProcessingInstruction { reason, position },
+            Self::Limit(limit) => Error::Limit { limit, position },
+        }
+    }
+}
+
+impl From<quick_xml::Error> for Cause {
+    fn from(err: quick_xml::Error) -> Self {
+        Cause::Xml(err)
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+};
+
+fn text_replacement(char: char) -> Option<&'static str> {
+    match char {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        // Required after `]]`, escaped everywhere for simplicity.
+        '>' => Some("&gt;"),
+        // Would otherwise be turned into a line feed when parsed.
+        '\r' => Some("&#13;"),
+        _ => None,
+    }
+}
+
+fn attribute_replacement(char: char) -> Option<&'static str> {
+    match char {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '"' => Some("&quot;"),
+        // Whitespace would otherwise be normalized to spaces when parsed.
+        '\t' => Some("&#9;"),
+        '\n' => Some("&#10;"),
+        '\r' => Some("&#13;"),
+        _ => None,
+    }
+}
+
+/** Escape text for use between tags. */
+pub(crate) fn escape_text(raw: &str) -> Cow<'_, str> {
+    if !raw.contains(|char| text_replacement(char).is_some()) {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut escaped = String::with_capacity(raw.len() + 8);
+    // Writing to a String cannot fail.
+    let _ = write_text(&mut escaped, raw);
+    Cow::Owned(escaped)
+}
+
+/** Write text escaped for use between tags. */
+pub(crate) fn write_text(out: &mut impl Write, raw: &str) -> fmt::Result {
+    write_escaped(out, raw, text_replacement)
+}
+
+/** Write text escaped for use as a double-quoted attribute value. */
+pub(crate) fn write_attribute(out: &mut impl Write, raw: &str) -> fmt::Result {
+    write_escaped(out, raw, attribute_replacement)
+}
+
+/** Write text escaped for use within ```<![CDATA[...]]>```.
+
+CDATA cannot contain ```]]>```, so the section is split in two at every occurrence. */
+pub(crate) fn write_cdata(out: &mut impl Write, raw: &str) -> fmt::Result {
+    let mut parts = raw.split("]]>");
+    if let Some(first) = parts.next() {
+        out.write_str(first)?;
+    }
+    for part in parts {
+        out.write_str("]]]]><![CDATA[>")?;
+        out.write_str(part)?;
+    }
+    Ok(())
+}
+
+/** Write text for use within ```<!--...-->```.
+
+Comments cannot contain ```--``` or end in ```-```, and there is no way to escape it,
+so a space is inserted after every hyphen followed by another one, and after a trailing one. */
+pub(crate) fn write_comment(out: &mut impl Write, raw: &str) -> fmt::Result {
+    write_split(out, raw, "--")?;
+    if raw.ends_with('-') {
+        out.write_char(' ')?;
+    }
+    Ok(())
+}
+
+/** Write text for use within ```<?...?>```.
+
+Processing instructions cannot contain ```?>```, so a space is inserted between the two characters. */
+pub(crate) fn write_instruction(out: &mut impl Write, raw: &str) -> fmt::Result {
+    write_split(out, raw, "?>")
+}
+
+/** Write text, inserting a space between the first and second character of every occurrence of a two character pattern. */
+fn write_split(out: &mut impl Write, raw: &str, pattern: &str) -> fmt::Result {
+    let mut rest = raw;
+    while let Some(i) = rest.find(pattern) {
+        out.write_str(&rest[..=i])?;
+        out.write_char(' ')?;
+        rest = &rest[i + 1..];
+    }
+    out.write_str(rest)
+}
+
+fn write_escaped(
+    out: &mut impl Write,
+    raw: &str,
+    replacement: fn(char) -> Option<&'static str>,
+) -> fmt::Result {
+    let mut unescaped = 0;
+
+    for (i, char) in raw.char_indices() {
+        if let Some(replacement) = replacement(char) {
+            out.write_str(&raw[unescaped..i])?;
+            out.write_str(replacement)?;
+            unescaped = i + char.len_utf8();
+        }
+    }
+
+    out.write_str(&raw[unescaped..])
+}
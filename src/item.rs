@@ -1,43 +1,51 @@
 use std::fmt::Display;
 
-use crate::Element;
+use crate::{
+    serialize::Serializer, DocType, Element, ProcessingInstruction, SerializeOptions, Span,
+};
 
 /** Any XML item. May be a comment, an element, a bit of text, ... */
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Item {
     /** Element ```<tag attr="value">...</tag>``` or ```<tag attr="value" />```. */
     Element(Element),
-    /** Comment ```<!-- ... -->```. */
+    /** Comment ```<!-- ... -->```.
+
+    Comments cannot contain ```--``` or end in ```-```, so a space is written after such hyphens. */
     Comment(String),
-    /** Escaped character data between tags. */
+    /** Character data between tags, with entity and character references decoded. */
     Text(String),
-    /** Document type definition data (DTD) stored in ```<!DOCTYPE ...>```. */
-    DocType(String),
+    /** Document type declaration ```<!DOCTYPE ...>```. */
+    DocType(DocType),
     /** Unescaped character data stored in ```<![CDATA[...]]>```. */
     CData(String),
-    /** XML declaration ```<?xml ...?>```. */
+    /** XML declaration ```<?xml ...?>```.
+
+    It cannot contain ```?>```, so a space is written between the two characters. See [`XmlDecl`](crate::XmlDecl) for a validated declaration. */
     Decl(String),
-    /** Processing instruction ```<?...?>```. */
-    PI(String),
+    /** Processing instruction ```<?target data?>```. */
+    PI(ProcessingInstruction),
 }
 
 impl Item {
     pub fn new_element(name: String) -> Item {
         Item::Element(Element::new(name))
     }
+
+    /** Get the span of the item in the input, if it is an element parsed with spans.
+
+    Spans of other items are available from their parent through [`Element::child_span`],
+    or from [`parse_with_spans`](crate::parse_with_spans) if they are at the top level. */
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Element(element) => element.span(),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Item {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str = &match self {
-            Self::Element(element) => element.to_string(),
-            Self::Text(text) => text.to_owned(),
-            Self::Comment(comment) => format!("<!--{comment}-->"),
-            Self::DocType(doctype) => format!("<!DOCTYPE {doctype}>"),
-            Self::Decl(decl) => format!("<?{decl}?>"),
-            Self::CData(cdata) => format!("<![CDATA[{cdata}]]>"),
-            Self::PI(pi) => format!("<?{pi}?>"),
-        };
-
-        write!(f, "{str}")
+        Serializer::new(f, &SerializeOptions::new()).items(std::slice::from_ref(self))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,362 @@
+use std::{collections::VecDeque, iter::FusedIterator, slice};
+
+use crate::{Item, NodePath};
+
+/** Iterator over the descendants of an element in depth-first order, i.e. the order they appear in the document.
+
+Created by [`Element::descendants`](crate::Element::descendants). */
+#[derive(Clone, Debug)]
+pub struct Descendants<'a> {
+    /** Remaining children of the element and of each open descendant, innermost last, with their total number. */
+    stack: Vec<(slice::Iter<'a, Item>, usize)>,
+}
+
+impl<'a> Descendants<'a> {
+    pub(crate) fn new(children: &'a [Item]) -> Self {
+        Descendants {
+            stack: vec![(children.iter(), children.len())],
+        }
+    }
+
+    /** Also return the path of each item, relative to the element the iterator was created for. */
+    pub fn with_paths(self) -> WithPaths<Self> {
+        WithPaths { inner: self }
+    }
+
+    /** Get the path of the item returned last. The children of an element are opened right after it is returned. */
+    fn path(&self, last: &Item) -> NodePath {
+        let open = match last {
+            Item::Element(_) => self.stack.len().saturating_sub(1),
+            _ => self.stack.len(),
+        };
+        path(&self.stack[..open])
+    }
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = &'a Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (children, _) = self.stack.last_mut()?;
+            match children.next() {
+                Some(item) => {
+                    if let Item::Element(element) = item {
+                        self.stack
+                            .push((element.children.iter(), element.children.len()));
+                    }
+                    return Some(item);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for Descendants<'_> {}
+
+/** Iterator over the descendants of an element in breadth-first order, i.e. level by level.
+
+Created by [`Element::descendants_breadth_first`](crate::Element::descendants_breadth_first). */
+#[derive(Clone, Debug)]
+pub struct DescendantsBreadthFirst<'a> {
+    /** Remaining children of the elements whose children have not been visited yet, in order,
+    with their total number and the position of their parent in the trail. */
+    queue: VecDeque<(slice::Iter<'a, Item>, usize, Option<usize>)>,
+    /** Position of each element returned so far, as the position of its parent in the trail and its own index. */
+    trail: Vec<(Option<usize>, usize)>,
+}
+
+impl<'a> DescendantsBreadthFirst<'a> {
+    pub(crate) fn new(children: &'a [Item]) -> Self {
+        DescendantsBreadthFirst {
+            queue: VecDeque::from([(children.iter(), children.len(), None)]),
+            trail: Vec::new(),
+        }
+    }
+
+    /** Also return the path of each item, relative to the element the iterator was created for. */
+    pub fn with_paths(self) -> WithPaths<Self> {
+        WithPaths { inner: self }
+    }
+
+    /** Get the path of the item returned last. */
+    fn path(&self) -> NodePath {
+        let Some((children, len, mut parent)) = self.queue.front() else {
+            return NodePath::default();
+        };
+
+        let mut indices = vec![index(children.len(), *len)];
+        while let Some(&(grandparent, index)) = parent.and_then(|parent| self.trail.get(parent)) {
+            indices.push(index);
+            parent = grandparent;
+        }
+        indices.reverse();
+        NodePath(indices)
+    }
+}
+
+impl<'a> Iterator for DescendantsBreadthFirst<'a> {
+    type Item = &'a Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (children, len, parent) = self.queue.front_mut()?;
+            match children.next() {
+                Some(item) => {
+                    if let Item::Element(element) = item {
+                        self.trail.push((*parent, index(children.len(), *len)));
+                        let position = Some(self.trail.len() - 1);
+                        let children = (element.children.iter(), element.children.len(), position);
+                        self.queue.push_back(children);
+                    }
+                    return Some(item);
+                }
+                None => {
+                    self.queue.pop_front();
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for DescendantsBreadthFirst<'_> {}
+
+/** Iterator over the descendants of an element at a certain depth, in document order.
+
+Created by [`Element::descendants_at_depth`](crate::Element::descendants_at_depth). */
+#[derive(Clone, Debug)]
+pub struct DescendantsAtDepth<'a> {
+    /** Remaining children of the element and of each open descendant, innermost last, with their total number. */
+    stack: Vec<(slice::Iter<'a, Item>, usize)>,
+    depth: usize,
+}
+
+impl<'a> DescendantsAtDepth<'a> {
+    pub(crate) fn new(children: &'a [Item], depth: usize) -> Self {
+        // The element itself is at depth zero, so there is nothing to iterate over.
+        let stack = if depth == 0 {
+            Vec::new()
+        } else {
+            vec![(children.iter(), children.len())]
+        };
+        DescendantsAtDepth { stack, depth }
+    }
+
+    /** Also return the path of each item, relative to the element the iterator was created for. */
+    pub fn with_paths(self) -> WithPaths<Self> {
+        WithPaths { inner: self }
+    }
+}
+
+impl<'a> Iterator for DescendantsAtDepth<'a> {
+    type Item = &'a Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len();
+            let (children, _) = self.stack.last_mut()?;
+            match children.next() {
+                Some(item) if depth == self.depth => return Some(item),
+                Some(Item::Element(element)) => self
+                    .stack
+                    .push((element.children.iter(), element.children.len())),
+                Some(_) => (),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for DescendantsAtDepth<'_> {}
+
+/** Iterator over the descendants of an element which are not elements, in document order, allowing modification.
+
+Created by [`Element::descendants_mut`](crate::Element::descendants_mut). */
+#[derive(Debug)]
+pub struct DescendantsMut<'a> {
+    /** Remaining children of the element and of each open descendant, innermost last, with their total number. */
+    stack: Vec<(slice::IterMut<'a, Item>, usize)>,
+}
+
+impl<'a> DescendantsMut<'a> {
+    pub(crate) fn new(children: &'a mut [Item]) -> Self {
+        let len = children.len();
+        DescendantsMut {
+            stack: vec![(children.iter_mut(), len)],
+        }
+    }
+
+    /** Also return the path of each item, relative to the element the iterator was created for. */
+    pub fn with_paths(self) -> WithPaths<Self> {
+        WithPaths { inner: self }
+    }
+}
+
+impl<'a> Iterator for DescendantsMut<'a> {
+    type Item = &'a mut Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (children, _) = self.stack.last_mut()?;
+            match children.next() {
+                Some(Item::Element(element)) => {
+                    let len = element.children.len();
+                    self.stack.push((element.children.iter_mut(), len))
+                }
+                Some(item) => return Some(item),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for DescendantsMut<'_> {}
+
+/** Iterator over the descendants of an element at a certain depth, in document order, allowing modification.
+
+Created by [`Element::descendants_at_depth_mut`](crate::Element::descendants_at_depth_mut). */
+#[derive(Debug)]
+pub struct DescendantsAtDepthMut<'a> {
+    /** Remaining children of the element and of each open descendant, innermost last, with their total number. */
+    stack: Vec<(slice::IterMut<'a, Item>, usize)>,
+    depth: usize,
+}
+
+impl<'a> DescendantsAtDepthMut<'a> {
+    pub(crate) fn new(children: &'a mut [Item], depth: usize) -> Self {
+        let len = children.len();
+        let stack = if depth == 0 {
+            Vec::new()
+        } else {
+            vec![(children.iter_mut(), len)]
+        };
+        DescendantsAtDepthMut { stack, depth }
+    }
+
+    /** Also return the path of each item, relative to the element the iterator was created for. */
+    pub fn with_paths(self) -> WithPaths<Self> {
+        WithPaths { inner: self }
+    }
+}
+
+impl<'a> Iterator for DescendantsAtDepthMut<'a> {
+    type Item = &'a mut Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len();
+            let (children, _) = self.stack.last_mut()?;
+            match children.next() {
+                Some(item) if depth == self.depth => return Some(item),
+                Some(Item::Element(element)) => {
+                    let len = element.children.len();
+                    self.stack.push((element.children.iter_mut(), len))
+                }
+                Some(_) => (),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for DescendantsAtDepthMut<'_> {}
+
+/** Iterator returning the items of another iterator along with their paths.
+
+Created by the `with_paths` method of the descendant iterators, e.g. [`Descendants::with_paths`].
+```rust
+# use larix::*;
+let mut items = parse("<a><b>1</b><c><b>2</b></c></a>")?;
+let Item::Element(a) = &mut items[0] else {
+    panic!();
+};
+
+// Record where the matches are, then edit them later.
+let paths: Vec<NodePath> = a
+    .descendants()
+    .with_paths()
+    .filter(|(_, item)| matches!(item, Item::Element(el) if el.name == "b"))
+    .map(|(path, _)| path)
+    .collect();
+assert_eq!(paths, [NodePath(vec![0]), NodePath(vec![1, 0])]);
+
+for path in paths.iter().rev() {
+    a.remove_at_path(path);
+}
+assert_eq!(a.to_string(), "<a><c></c></a>");
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug)]
+pub struct WithPaths<I> {
+    inner: I,
+}
+
+impl<'a> Iterator for WithPaths<Descendants<'a>> {
+    type Item = (NodePath, &'a Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some((self.inner.path(item), item))
+    }
+}
+
+impl<'a> Iterator for WithPaths<DescendantsBreadthFirst<'a>> {
+    type Item = (NodePath, &'a Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some((self.inner.path(), item))
+    }
+}
+
+impl<'a> Iterator for WithPaths<DescendantsAtDepth<'a>> {
+    type Item = (NodePath, &'a Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some((path(&self.inner.stack), item))
+    }
+}
+
+impl<'a> Iterator for WithPaths<DescendantsMut<'a>> {
+    type Item = (NodePath, &'a mut Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some((path(&self.inner.stack), item))
+    }
+}
+
+impl<'a> Iterator for WithPaths<DescendantsAtDepthMut<'a>> {
+    type Item = (NodePath, &'a mut Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some((path(&self.inner.stack), item))
+    }
+}
+
+impl<I> FusedIterator for WithPaths<I> where Self: Iterator {}
+
+/** Get the path of the item returned last from the innermost of the open levels. */
+fn path<I: ExactSizeIterator>(open: &[(I, usize)]) -> NodePath {
+    NodePath(
+        open.iter()
+            .map(|(children, len)| index(children.len(), *len))
+            .collect(),
+    )
+}
+
+/** Get the index of the item returned last, given the number of remaining items and the total number of items. */
+fn index(remaining: usize, len: usize) -> usize {
+    len.saturating_sub(remaining).saturating_sub(1)
+}
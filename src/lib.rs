@@ -1,10 +1,57 @@
-pub use quick_xml::Error;
+mod error;
+pub use error::*;
+
+mod escape;
 
 mod util;
-pub use util::{parse, parse_trimmed, stringify};
+pub use util::{
+    parse, parse_file, parse_reader, parse_reader_with, parse_trimmed, parse_with,
+    parse_with_spans, parse_with_warnings, stringify,
+};
+
+mod options;
+pub use options::*;
+
+mod span;
+pub use span::{AttributeSpan, Span};
 
 mod item;
 pub use item::*;
 
 mod element;
 pub use element::*;
+
+mod iter;
+pub use iter::*;
+
+mod path;
+pub use path::*;
+
+mod visit;
+pub use visit::*;
+
+mod tree;
+pub use tree::*;
+
+mod document;
+pub use document::*;
+
+mod pi;
+pub use pi::ProcessingInstruction;
+
+mod decl;
+pub use decl::*;
+
+mod doctype;
+pub use doctype::*;
+
+mod dtd;
+
+mod attributes;
+pub use attributes::*;
+
+mod namespace;
+pub use namespace::{XMLNS_NAMESPACE, XML_NAMESPACE};
+
+mod serialize;
+pub use serialize::{stringify_with, write_items, write_items_with, SerializeOptions};
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use quick_xml::Error as XmlError;
+
+use crate::Attributes;
+
+/** Namespace which the ```xml``` prefix is always bound to. */
+pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/** Namespace of attributes declaring namespaces, e.g. ```xmlns:svg="..."```. */
+pub const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/** Split a qualified name like ```svg:rect``` into its prefix and local name. */
+pub(crate) fn split_name(name: &str) -> (Option<&str>, &str) {
+    match name.split_once(':') {
+        Some((prefix, local_name)) => (Some(prefix), local_name),
+        None => (None, name),
+    }
+}
+
+/** Find an attribute with the same namespace and local name as an earlier one of the element,
+e.g. ```p:x``` and ```q:x``` with both prefixes bound to the same namespace.
+
+Returns the names of the earlier attribute and of the duplicate. Namespaces must have been resolved. */
+pub(crate) fn duplicate_attribute(attributes: &Attributes) -> Option<(String, String)> {
+    let mut seen = HashMap::new();
+
+    attributes.list.iter().find_map(|attr| {
+        let namespace = attr.namespace.as_deref()?;
+        seen.insert((namespace, attr.local_name()), &attr.name)
+            .map(|earlier| (earlier.clone(), attr.name.clone()))
+    })
+}
+
+/** Check that a name is either unprefixed or a prefix and a local name, both of them not empty and without colons.
+
+Malformed names are reported like undeclared prefixes, with the whole name. */
+fn check_name(name: &str) -> Result<(), XmlError> {
+    match split_name(name) {
+        (Some(prefix), local_name)
+            if prefix.is_empty() || local_name.is_empty() || local_name.contains(':') =>
+        {
+            Err(XmlError::UnknownPrefix(name.as_bytes().to_vec()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/** Namespace declarations in scope while parsing. */
+pub(crate) struct Scopes {
+    /** Prefixes and the namespaces they are bound to, innermost last. The default namespace has an empty prefix. */
+    bindings: Vec<(String, Option<String>)>,
+    /** Number of bindings declared by each open element. */
+    declared: Vec<usize>,
+}
+
+impl Scopes {
+    pub(crate) fn new() -> Self {
+        Scopes {
+            bindings: Vec::new(),
+            declared: Vec::new(),
+        }
+    }
+
+    /** Enter an element, declaring the namespaces bound by its attributes, and resolve its names. */
+    pub(crate) fn enter(
+        &mut self,
+        name: &str,
+        attributes: &mut Attributes,
+    ) -> Result<Option<String>, XmlError> {
+        check_name(name)?;
+        for attr in attributes.list.iter() {
+            check_name(&attr.name)?;
+        }
+
+        let mut declared = 0;
+
+        for attr in attributes.list.iter() {
+            let prefix = match split_name(&attr.name) {
+                (None, "xmlns") => "",
+                (Some("xmlns"), prefix) => prefix,
+                _ => continue,
+            };
+
+            let namespace = &attr.value;
+            // Only the default namespace can be undeclared, prefixes cannot be bound to no namespace.
+            let reserved = prefix == "xmlns"
+                || (prefix == "xml") != (namespace == XML_NAMESPACE)
+                || namespace == XMLNS_NAMESPACE
+                || (!prefix.is_empty() && namespace.is_empty());
+            if reserved {
+                return Err(XmlError::InvalidPrefixBind {
+                    prefix: prefix.as_bytes().to_vec(),
+                    namespace: namespace.as_bytes().to_vec(),
+                });
+            }
+
+            // An empty namespace undeclares the default namespace.
+            let namespace = Some(namespace.clone()).filter(|namespace| !namespace.is_empty());
+            self.bindings.push((prefix.to_owned(), namespace));
+            declared += 1;
+        }
+
+        self.declared.push(declared);
+
+        for attr in attributes.list.iter_mut() {
+            attr.namespace = match split_name(&attr.name) {
+                (None, "xmlns") | (Some("xmlns"), _) => Some(String::from(XMLNS_NAMESPACE)),
+                // Unprefixed attributes are in no namespace, not in the default one.
+                (None, _) => None,
+                (Some(prefix), _) => self.resolve(prefix)?,
+            };
+        }
+
+        self.resolve(split_name(name).0.unwrap_or_default())
+    }
+
+    /** Leave the innermost element, forgetting the namespaces it declared. */
+    pub(crate) fn leave(&mut self) {
+        let declared = self.declared.pop().unwrap_or_default();
+        self.bindings.truncate(self.bindings.len() - declared);
+    }
+
+    fn resolve(&self, prefix: &str) -> Result<Option<String>, XmlError> {
+        if prefix == "xml" {
+            return Ok(Some(String::from(XML_NAMESPACE)));
+        }
+
+        match self
+            .bindings
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound == prefix)
+        {
+            Some((_, namespace)) => Ok(namespace.clone()),
+            None if prefix.is_empty() => Ok(None),
+            None => Err(XmlError::UnknownPrefix(prefix.as_bytes().to_vec())),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+/** Settings for parsing XML.
+
+```rust
+# use larix::*;
+let options = ParseOptions::new().trim_text(true).drop_comments(true);
+
+let items = parse_with("<a> <!-- comment --> text </a>", &options)?;
+
+let Item::Element(a) = &items[0] else {
+    panic!();
+};
+assert_eq!(a.children.len(), 1);
+assert_eq!(a.get_text_content(), "text");
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub(crate) trim_text: bool,
+    pub(crate) drop_comments: bool,
+    pub(crate) drop_processing_instructions: bool,
+    pub(crate) merge_cdata: bool,
+    pub(crate) reject_doctype: bool,
+    pub(crate) lenient_attributes: bool,
+    pub(crate) raw_values: bool,
+    pub(crate) namespaces: bool,
+    pub(crate) spans: bool,
+    pub(crate) max_entity_depth: usize,
+    pub(crate) max_entity_expansion: usize,
+    pub(crate) max_depth: usize,
+    pub(crate) max_input_size: u64,
+    pub(crate) max_attributes: usize,
+    pub(crate) max_attribute_length: usize,
+    pub(crate) max_text_length: usize,
+    pub(crate) max_items: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            trim_text: false,
+            drop_comments: false,
+            drop_processing_instructions: false,
+            merge_cdata: false,
+            reject_doctype: false,
+            lenient_attributes: false,
+            raw_values: false,
+            namespaces: false,
+            spans: false,
+            max_entity_depth: 16,
+            max_entity_expansion: 1 << 20,
+            max_depth: usize::MAX,
+            max_input_size: u64::MAX,
+            max_attributes: usize::MAX,
+            max_attribute_length: usize::MAX,
+            max_text_length: usize::MAX,
+            max_items: usize::MAX,
+        }
+    }
+}
+
+impl ParseOptions {
+    /** Options which parse XML as it is, without dropping or changing anything. */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /** Whether to trim whitespace from the start and end of text. Text consisting only of whitespace is dropped. */
+    pub fn trim_text(mut self, trim_text: bool) -> Self {
+        self.trim_text = trim_text;
+        self
+    }
+
+    /** Whether to leave out comments. */
+    pub fn drop_comments(mut self, drop_comments: bool) -> Self {
+        self.drop_comments = drop_comments;
+        self
+    }
+
+    /** Whether to leave out processing instructions. The XML declaration is kept. */
+    pub fn drop_processing_instructions(mut self, drop_processing_instructions: bool) -> Self {
+        self.drop_processing_instructions = drop_processing_instructions;
+        self
+    }
+
+    /** Whether to turn CDATA sections into text, merging them with adjacent text. */
+    pub fn merge_cdata(mut self, merge_cdata: bool) -> Self {
+        self.merge_cdata = merge_cdata;
+        self
+    }
+
+    /** Whether to fail with [`Error::DocType`](crate::Error::DocType) if the input contains a DOCTYPE. */
+    pub fn reject_doctype(mut self, reject_doctype: bool) -> Self {
+        self.reject_doctype = reject_doctype;
+        self
+    }
+
+    /** Whether to skip malformed attributes, such as duplicates or unquoted values, instead of failing.
+
+    Skipped attributes are reported as warnings by [`parse_with_warnings`](crate::parse_with_warnings). */
+    pub fn lenient_attributes(mut self, lenient_attributes: bool) -> Self {
+        self.lenient_attributes = lenient_attributes;
+        self
+    }
+
+    /** Whether to keep text and attribute values as they appear in the input.
+
+    By default, entity references such as ```&amp;``` and character references such as ```&#x20AC;``` are decoded.
+    Serialization escapes text and attribute values, so raw values are escaped a second time when written,
+    unless [`SerializeOptions::raw_values`](crate::SerializeOptions::raw_values) is set. */
+    pub fn raw_values(mut self, raw_values: bool) -> Self {
+        self.raw_values = raw_values;
+        self
+    }
+
+    /** Whether to resolve the namespaces of elements and attributes.
+
+    The namespaces are available through [`Element::namespace`](crate::Element::namespace)
+    and [`Attribute::namespace`](crate::Attribute::namespace).
+    Undeclared prefixes, prefixes bound to no namespace, names with an empty prefix or local name
+    and attributes with the same namespace and local name cause an error.
+    The latter are skipped instead if [`lenient_attributes`](Self::lenient_attributes) is set. */
+    pub fn namespaces(mut self, namespaces: bool) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+
+    /** Whether to record where each element was found in the input.
+
+    The spans are available through [`Element::span`](crate::Element::span) and related methods. */
+    pub fn spans(mut self, spans: bool) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /** How deeply entity references may be nested within the replacement texts of entities. Defaults to 16.
+
+    Entities are declared in the internal subset of the DOCTYPE. Exceeding the limit fails with [`Limit::EntityDepth`](crate::Limit::EntityDepth). */
+    pub fn max_entity_depth(mut self, max_entity_depth: usize) -> Self {
+        self.max_entity_depth = max_entity_depth;
+        self
+    }
+
+    /** How many bytes of replacement text may be inserted by entity references in total. Defaults to 1 MiB.
+
+    This guards against inputs like the "billion laughs", whose entities expand exponentially.
+    Exceeding the limit fails with [`Limit::EntityExpansion`](crate::Limit::EntityExpansion). */
+    pub fn max_entity_expansion(mut self, max_entity_expansion: usize) -> Self {
+        self.max_entity_expansion = max_entity_expansion;
+        self
+    }
+
+    /** How deeply elements may be nested, counting the top level as depth 1. Unlimited by default.
+
+    Exceeding the limit fails with [`Limit::Depth`](crate::Limit::Depth). */
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /** How many bytes of input may be read. Unlimited by default.
+
+    Readers are not read past the limit. Exceeding it fails with [`Limit::InputSize`](crate::Limit::InputSize). */
+    pub fn max_input_size(mut self, max_input_size: u64) -> Self {
+        self.max_input_size = max_input_size;
+        self
+    }
+
+    /** How many attributes an element may have. Unlimited by default.
+
+    Exceeding the limit fails with [`Limit::Attributes`](crate::Limit::Attributes). */
+    pub fn max_attributes(mut self, max_attributes: usize) -> Self {
+        self.max_attributes = max_attributes;
+        self
+    }
+
+    /** How many bytes an attribute value may have after decoding. Unlimited by default.
+
+    Exceeding the limit fails with [`Limit::AttributeLength`](crate::Limit::AttributeLength). */
+    pub fn max_attribute_length(mut self, max_attribute_length: usize) -> Self {
+        self.max_attribute_length = max_attribute_length;
+        self
+    }
+
+    /** How many bytes a text or CDATA item may have after decoding. Unlimited by default.
+
+    Exceeding the limit fails with [`Limit::TextLength`](crate::Limit::TextLength). */
+    pub fn max_text_length(mut self, max_text_length: usize) -> Self {
+        self.max_text_length = max_text_length;
+        self
+    }
+
+    /** How many items may be parsed in total, at all depths. Unlimited by default.
+
+    Exceeding the limit fails with [`Limit::Items`](crate::Limit::Items). */
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+}
@@ -0,0 +1,42 @@
+/** Address of an item within an element, as the index of each element to descend into, followed by the index of the item.
+
+E.g. ```[1, 0]``` is the first child of the second child. Paths do not borrow the tree,
+so they can be kept and used to revisit or edit the items later, as long as the tree has not been restructured.
+Paths are ordered like the items they address appear in the document.
+```rust
+# use larix::*;
+let items = parse("<a><b /><c>text</c></a>")?;
+let Item::Element(a) = &items[0] else {
+    panic!();
+};
+
+let text = a.get_path(&NodePath(vec![1, 0]));
+assert_eq!(text.map(Item::to_string).as_deref(), Some("text"));
+assert!(a.get_path(&NodePath(vec![0, 0])).is_none());
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodePath(pub Vec<usize>);
+
+impl NodePath {
+    /** Get the path of the element containing the addressed item, or `None` if this is a child of the element the path is relative to. */
+    pub fn parent(&self) -> Option<NodePath> {
+        match self.0.split_last() {
+            Some((_, [])) | None => None,
+            Some((_, parent)) => Some(NodePath(parent.to_vec())),
+        }
+    }
+
+    /** Get the path of the item at the given index within the addressed element. */
+    pub fn child(&self, index: usize) -> NodePath {
+        let mut path = self.0.clone();
+        path.push(index);
+        NodePath(path)
+    }
+}
+
+impl From<Vec<usize>> for NodePath {
+    fn from(indices: Vec<usize>) -> Self {
+        NodePath(indices)
+    }
+}
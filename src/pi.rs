@@ -0,0 +1,130 @@
+use std::fmt::Display;
+
+use crate::PIError;
+
+/** Processing instruction ```<?target data?>```, e.g. ```<?xml-stylesheet href="style.css"?>```.
+
+Instructions are validated when created, so they can always be written back as they are.
+In particular, targets named ```xml``` in any case are refused, as they are reserved for the XML declaration.
+```rust
+# use larix::*;
+let items = parse(r#"<?xml-stylesheet href="style.css" type="text/css"?><a />"#)?;
+let Item::PI(pi) = &items[0] else {
+    panic!();
+};
+
+assert_eq!(pi.target(), "xml-stylesheet");
+assert_eq!(pi.pseudo_attribute("href"), Some("style.css"));
+
+assert!(ProcessingInstruction::new(String::from("XML"), String::new()).is_err());
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProcessingInstruction {
+    target: String,
+    data: String,
+}
+
+impl ProcessingInstruction {
+    /** Create a processing instruction, checking that it can be written as XML. */
+    pub fn new(target: String, data: String) -> Result<Self, PIError> {
+        check_target(&target)?;
+        check_data(&data)?;
+        Ok(ProcessingInstruction { target, data })
+    }
+
+    /** Get the name of the application the instruction is directed to. */
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /** Get everything after the target, without the whitespace separating them. */
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /** Replace the data, checking that it can be written as XML. */
+    pub fn set_data(&mut self, data: String) -> Result<(), PIError> {
+        check_data(&data)?;
+        self.data = data;
+        Ok(())
+    }
+
+    /** Read the data as pseudo-attributes like ```href="style.css" type="text/css"```.
+
+    Values are returned as written, without decoding references.
+    Returns `None` if the data is not a list of pseudo-attributes. */
+    pub fn pseudo_attributes(&self) -> Option<Vec<(&str, &str)>> {
+        pseudo_attributes(&self.data)
+    }
+
+    /** Get the value of the pseudo-attribute with the given name. See [`pseudo_attributes`](Self::pseudo_attributes). */
+    pub fn pseudo_attribute(&self, name: &str) -> Option<&str> {
+        self.pseudo_attributes()?
+            .into_iter()
+            .find(|(attr, _)| *attr == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Display for ProcessingInstruction {
+    /** Write the content of the instruction, i.e. everything between ```<?``` and ```?>```. */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.target)?;
+        if !self.data.is_empty() {
+            write!(f, " {}", self.data)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_target(target: &str) -> Result<(), PIError> {
+    if target.eq_ignore_ascii_case("xml") {
+        return Err(PIError::ReservedTarget(target.to_owned()));
+    }
+    if target.is_empty() || target.contains(is_space) || target.contains("?>") {
+        return Err(PIError::InvalidTarget(target.to_owned()));
+    }
+    Ok(())
+}
+
+fn check_data(data: &str) -> Result<(), PIError> {
+    if data.contains("?>") {
+        return Err(PIError::InvalidData);
+    }
+    Ok(())
+}
+
+/** Split a list of pseudo-attributes like ```a="1" b='2'``` into names and values.
+
+Pseudo-attributes must be separated by whitespace. Returns `None` if the list is malformed. */
+pub(crate) fn pseudo_attributes(mut rest: &str) -> Option<Vec<(&str, &str)>> {
+    let mut attributes = Vec::new();
+
+    loop {
+        let trimmed = rest.trim_start_matches(is_space);
+        if trimmed.is_empty() {
+            return Some(attributes);
+        }
+        if !attributes.is_empty() && trimmed.len() == rest.len() {
+            return None;
+        }
+
+        let (name, after) = trimmed.split_once('=')?;
+        let name = name.trim_end_matches(is_space);
+        let after = after.trim_start_matches(is_space);
+
+        let quote = after.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let (value, after) = after[1..].split_once(quote)?;
+
+        if name.is_empty() || name.contains(is_space) {
+            return None;
+        }
+        attributes.push((name, value));
+        rest = after;
+    }
+}
+
+pub(crate) fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
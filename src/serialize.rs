@@ -0,0 +1,527 @@
+use std::{
+    fmt::{self, Write},
+    io,
+};
+
+use crate::{
+    escape::{write_attribute, write_cdata, write_comment, write_instruction, write_text},
+    Document, Element, Item,
+};
+
+/** Settings for writing XML.
+
+By default, XML is written compactly, exactly like [`Display`](std::fmt::Display) does.
+```rust
+# use larix::*;
+let items = parse(r#"<list><item id="1">One</item><item id="2" /></list>"#)?;
+
+let xml = stringify_with(&items, &SerializeOptions::pretty());
+
+assert_eq!(xml, r#"<list>
+  <item id="1">One</item>
+  <item id="2" />
+</list>"#);
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug)]
+pub struct SerializeOptions {
+    pub(crate) indent: Option<String>,
+    pub(crate) max_width: usize,
+    pub(crate) wrap_attributes: bool,
+    pub(crate) strip_whitespace: bool,
+    pub(crate) raw_values: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            indent: None,
+            max_width: usize::MAX,
+            wrap_attributes: false,
+            strip_whitespace: false,
+            raw_values: false,
+        }
+    }
+}
+
+impl SerializeOptions {
+    /** Options which write XML compactly, without adding any whitespace. */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /** Options which indent by two spaces and wrap attributes of tags longer than 80 characters. */
+    pub fn pretty() -> Self {
+        Self::new()
+            .indent(Some(String::from("  ")))
+            .max_width(80)
+            .wrap_attributes(true)
+    }
+
+    /** The string to indent each level of nesting with. `None` writes everything on one line.
+
+    Whitespace is never added to elements containing text or CDATA, as it could be significant there,
+    nor within elements marked with ```xml:space="preserve"```. */
+    pub fn indent(mut self, indent: Option<String>) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /** The number of characters after which a start tag is considered too long. */
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /** Whether to put each attribute of a start tag on its own line if the tag is too long. Requires an indent. */
+    pub fn wrap_attributes(mut self, wrap_attributes: bool) -> Self {
+        self.wrap_attributes = wrap_attributes;
+        self
+    }
+
+    /** Whether to treat text consisting only of whitespace as insignificant, replacing it by the indentation. Requires an indent.
+
+    This changes the content of the document, e.g. the text content of ```<p><b>a</b> <i>b</i></p>```.
+    It is meant for re-indenting documents which are known not to contain mixed content.
+    Elements marked with ```xml:space="preserve"``` are left untouched regardless. */
+    pub fn strip_whitespace(mut self, strip_whitespace: bool) -> Self {
+        self.strip_whitespace = strip_whitespace;
+        self
+    }
+
+    /** Whether to write text and attribute values as they are, without escaping them.
+
+    Meant for items parsed with [`ParseOptions::raw_values`](crate::ParseOptions::raw_values), whose values are still escaped.
+    Attribute values containing ```"``` are put in single quotes.
+    ```rust
+    # use larix::*;
+    const RAW: &str = r#"<a t="&amp;">&lt;</a>"#;
+    let items = parse_with(RAW, &ParseOptions::new().raw_values(true))?;
+
+    assert_eq!(stringify_with(&items, &SerializeOptions::new().raw_values(true)), RAW);
+    # Ok::<(), Error>(())
+    ```*/
+    pub fn raw_values(mut self, raw_values: bool) -> Self {
+        self.raw_values = raw_values;
+        self
+    }
+}
+
+/** Stringifies a list of XML items using the given options. */
+pub fn stringify_with(xml: &[Item], options: &SerializeOptions) -> String {
+    to_string(options, |serializer| serializer.items(xml))
+}
+
+/** Writes a list of XML items to a writer.
+
+The items are written as they are visited, without building the output in memory first.
+Many small writes are made, so the writer should be buffered.
+```rust
+# use larix::*;
+let items = parse("<a><b /></a>text")?;
+
+let mut out = Vec::new();
+write_items(&items, &mut out)?;
+
+assert_eq!(out, b"<a><b /></a>text");
+# Ok::<(), Box<dyn std::error::Error>>(())
+```*/
+pub fn write_items(xml: &[Item], out: &mut impl io::Write) -> io::Result<()> {
+    write_items_with(xml, out, &SerializeOptions::new())
+}
+
+/** Writes a list of XML items to a writer using the given options. */
+pub fn write_items_with(
+    xml: &[Item],
+    out: &mut impl io::Write,
+    options: &SerializeOptions,
+) -> io::Result<()> {
+    let mut out = IoWriter::new(out);
+    let result = Serializer::new(&mut out, options).items(xml);
+    out.finish(result)
+}
+
+impl Item {
+    /** Stringify the item using the given options. */
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        stringify_with(std::slice::from_ref(self), options)
+    }
+
+    /** Write the item to a writer. See [`write_items`]. */
+    pub fn write_to(&self, out: &mut impl io::Write) -> io::Result<()> {
+        write_items(std::slice::from_ref(self), out)
+    }
+
+    /** Write the item to a writer using the given options. See [`write_items`]. */
+    pub fn write_to_with(
+        &self,
+        out: &mut impl io::Write,
+        options: &SerializeOptions,
+    ) -> io::Result<()> {
+        write_items_with(std::slice::from_ref(self), out, options)
+    }
+}
+
+impl Element {
+    /** Stringify the element using the given options. */
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        to_string(options, |serializer| serializer.element(self, 0))
+    }
+
+    /** Write the element to a writer. See [`write_items`]. */
+    pub fn write_to(&self, out: &mut impl io::Write) -> io::Result<()> {
+        self.write_to_with(out, &SerializeOptions::new())
+    }
+
+    /** Write the element to a writer using the given options. See [`write_items`]. */
+    pub fn write_to_with(
+        &self,
+        out: &mut impl io::Write,
+        options: &SerializeOptions,
+    ) -> io::Result<()> {
+        let mut out = IoWriter::new(out);
+        let result = Serializer::new(&mut out, options).element(self, 0);
+        out.finish(result)
+    }
+}
+
+impl Document {
+    /** Stringify the document using the given options. */
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        to_string(options, |serializer| serializer.document(self))
+    }
+
+    /** Write the document to a writer. See [`write_items`]. */
+    pub fn write_to(&self, out: &mut impl io::Write) -> io::Result<()> {
+        self.write_to_with(out, &SerializeOptions::new())
+    }
+
+    /** Write the document to a writer using the given options. See [`write_items`]. */
+    pub fn write_to_with(
+        &self,
+        out: &mut impl io::Write,
+        options: &SerializeOptions,
+    ) -> io::Result<()> {
+        let mut out = IoWriter::new(out);
+        let result = Serializer::new(&mut out, options).document(self);
+        out.finish(result)
+    }
+}
+
+/** Write something to a new string using the given options. */
+fn to_string(
+    options: &SerializeOptions,
+    write: impl FnOnce(&mut Serializer<'_, String>) -> fmt::Result,
+) -> String {
+    let mut result = String::new();
+    // Writing to a String cannot fail.
+    let _ = write(&mut Serializer::new(&mut result, options));
+    result
+}
+
+/** Writes items according to the serialize options. */
+pub(crate) struct Serializer<'a, W> {
+    out: &'a mut W,
+    options: &'a SerializeOptions,
+}
+
+/** Whether whitespace is added between the items at the top level, and whether none has been written yet. */
+struct TopLevel {
+    indent: bool,
+    first: bool,
+}
+
+/** An element whose start tag has been written, but not yet its children and end tag. */
+struct Open<'e> {
+    element: &'e Element,
+    /** Index of the next child to write. */
+    next: usize,
+    depth: usize,
+    /** Whether to put each child on its own line. */
+    indent: bool,
+    /** Whether any child has been written. */
+    written: bool,
+}
+
+impl<'a, W: Write> Serializer<'a, W> {
+    pub(crate) fn new(out: &'a mut W, options: &'a SerializeOptions) -> Self {
+        Serializer { out, options }
+    }
+
+    /** Write items at the top level, each on its own line if indenting. */
+    pub(crate) fn items(&mut self, items: &[Item]) -> fmt::Result {
+        let mut top = TopLevel {
+            indent: self.indentable(items),
+            first: true,
+        };
+        for item in items {
+            self.top_level(item, &mut top)?;
+        }
+        Ok(())
+    }
+
+    /** Write the prolog, root element and epilog of a document. */
+    pub(crate) fn document(&mut self, document: &Document) -> fmt::Result {
+        let mut top = TopLevel {
+            indent: self.indentable(document.prolog()) && self.indentable(document.epilog()),
+            first: true,
+        };
+        for item in document.prolog() {
+            self.top_level(item, &mut top)?;
+        }
+        self.separate(&mut top)?;
+        self.element(document.root(), 0)?;
+        for item in document.epilog() {
+            self.top_level(item, &mut top)?;
+        }
+        Ok(())
+    }
+
+    /** Write an item at the top level, on its own line if indenting. */
+    fn top_level(&mut self, item: &Item, top: &mut TopLevel) -> fmt::Result {
+        if top.indent && is_whitespace(item) {
+            return Ok(());
+        }
+        self.separate(top)?;
+
+        match item {
+            Item::Element(element) => self.element(element, 0),
+            item => self.leaf(item),
+        }
+    }
+
+    /** Start a new line before a top level item if indenting, unless it is the first one. */
+    fn separate(&mut self, top: &mut TopLevel) -> fmt::Result {
+        if top.indent && !top.first {
+            self.out.write_char('\n')?;
+        }
+        top.first = false;
+        Ok(())
+    }
+
+    /** Whether whitespace may be added between the items, i.e. whether none of them is significant text. */
+    fn indentable(&self, items: &[Item]) -> bool {
+        self.options.indent.is_some()
+            && items.iter().all(|item| match item {
+                Item::Text(_) => self.options.strip_whitespace && is_whitespace(item),
+                Item::CData(_) => false,
+                _ => true,
+            })
+    }
+
+    /** Write an element and its descendants. */
+    pub(crate) fn element(&mut self, element: &Element, depth: usize) -> fmt::Result {
+        let indent = self.options.indent.is_some();
+
+        let mut stack = Vec::new();
+        stack.extend(self.open(element, depth, indent)?);
+
+        while let Some(open) = stack.last_mut() {
+            let element = open.element;
+            let skip = open.indent;
+            let next = element.children[open.next..]
+                .iter()
+                .position(|child| !(skip && is_whitespace(child)));
+
+            let Some(offset) = next else {
+                if open.indent && open.written {
+                    let depth = open.depth;
+                    self.newline(depth)?;
+                }
+                write!(self.out, "</{}>", element.name)?;
+                stack.pop();
+                continue;
+            };
+
+            let child = &element.children[open.next + offset];
+            open.next += offset + 1;
+            open.written = true;
+            let (depth, indent) = (open.depth + 1, open.indent);
+
+            if indent {
+                self.newline(depth)?;
+            }
+            match child {
+                Item::Element(child) => stack.extend(self.open(child, depth, indent)?),
+                child => self.leaf(child)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /** Write the start tag of an element. Returns the element if its children and end tag are still to be written. */
+    fn open<'e>(
+        &mut self,
+        element: &'e Element,
+        depth: usize,
+        indent: bool,
+    ) -> Result<Option<Open<'e>>, fmt::Error> {
+        let self_closing = element.self_closing && element.children.is_empty();
+        self.start_tag(element, depth, indent, self_closing)?;
+
+        if self_closing {
+            return Ok(None);
+        }
+
+        Ok(Some(Open {
+            element,
+            next: 0,
+            depth,
+            // Whitespace may be significant in mixed content, so it is left untouched.
+            indent: indent && !preserves_space(element) && self.indentable(&element.children),
+            written: false,
+        }))
+    }
+
+    /** Write an item which is not an element. */
+    fn leaf(&mut self, item: &Item) -> fmt::Result {
+        match item {
+            Item::Element(element) => self.element(element, 0),
+            Item::Text(text) if self.options.raw_values => self.out.write_str(text),
+            Item::Text(text) => write_text(self.out, text),
+            Item::Comment(comment) => {
+                self.out.write_str("<!--")?;
+                write_comment(self.out, comment)?;
+                self.out.write_str("-->")
+            }
+            Item::DocType(doctype) => write!(self.out, "<!DOCTYPE {doctype}>"),
+            Item::Decl(decl) => {
+                self.out.write_str("<?")?;
+                write_instruction(self.out, decl)?;
+                self.out.write_str("?>")
+            }
+            Item::CData(cdata) => {
+                self.out.write_str("<![CDATA[")?;
+                write_cdata(self.out, cdata)?;
+                self.out.write_str("]]>")
+            }
+            Item::PI(pi) => write!(self.out, "<?{pi}?>"),
+        }
+    }
+
+    fn start_tag(
+        &mut self,
+        element: &Element,
+        depth: usize,
+        indent: bool,
+        self_closing: bool,
+    ) -> fmt::Result {
+        let end = if self_closing { " />" } else { ">" };
+
+        let wrap = match &self.options.indent {
+            Some(indent_str)
+                if indent && self.options.wrap_attributes && element.attributes.len() > 1 =>
+            {
+                let mut width = Width(
+                    indent_str.chars().count() * depth
+                        + 1
+                        + element.name.chars().count()
+                        + end.len(),
+                );
+                for attr in &element.attributes {
+                    width.0 += attr.name.chars().count() + 2;
+                    write_value(&mut width, &attr.value, self.options.raw_values)?;
+                }
+                (width.0 > self.options.max_width).then_some(indent_str)
+            }
+            _ => None,
+        };
+
+        write!(self.out, "<{}", element.name)?;
+        for attr in &element.attributes {
+            match wrap {
+                Some(indent_str) => {
+                    self.out.write_char('\n')?;
+                    write_indent(self.out, indent_str, depth + 1)?;
+                }
+                None => self.out.write_char(' ')?,
+            }
+            write!(self.out, "{}=", attr.name)?;
+            write_value(self.out, &attr.value, self.options.raw_values)?;
+        }
+        self.out.write_str(end)
+    }
+
+    /** Start a new line, indented to the given depth. */
+    fn newline(&mut self, depth: usize) -> fmt::Result {
+        self.out.write_char('\n')?;
+        if let Some(indent) = &self.options.indent {
+            write_indent(self.out, indent, depth)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_indent(out: &mut impl Write, indent: &str, depth: usize) -> fmt::Result {
+    for _ in 0..depth {
+        out.write_str(indent)?;
+    }
+    Ok(())
+}
+
+/** Write an attribute value in quotes, escaped unless it is raw. */
+fn write_value(out: &mut impl Write, value: &str, raw: bool) -> fmt::Result {
+    let quote = if raw && value.contains('"') {
+        '\''
+    } else {
+        '"'
+    };
+    out.write_char(quote)?;
+    if raw {
+        out.write_str(value)?;
+    } else {
+        write_attribute(out, value)?;
+    }
+    out.write_char(quote)
+}
+
+/** Whether the item is text consisting only of whitespace. */
+fn is_whitespace(item: &Item) -> bool {
+    matches!(item, Item::Text(text) if text.chars().all(char::is_whitespace))
+}
+
+/** Whether the element is marked with ```xml:space="preserve"```. Its descendants are never indented either. */
+fn preserves_space(element: &Element) -> bool {
+    element.attributes.get("xml:space").map(String::as_str) == Some("preserve")
+}
+
+/** Counts the characters written to it. */
+struct Width(usize);
+
+impl Write for Width {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+/** Adapts an [`io::Write`] for use as a [`fmt::Write`], remembering the I/O error that occurred. */
+struct IoWriter<'a, W> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> IoWriter<'a, W> {
+    fn new(inner: &'a mut W) -> Self {
+        IoWriter { inner, error: None }
+    }
+
+    /** Turn the result of writing into the underlying I/O result. */
+    fn finish(self, result: fmt::Result) -> io::Result<()> {
+        match (result, self.error) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(err)) => Err(err),
+            (Err(_), None) => Err(io::Error::new(io::ErrorKind::Other, "formatter error")),
+        }
+    }
+}
+
+impl<W: io::Write> Write for IoWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use crate::util::Lines;
+
+/** Range of bytes within the parsed input. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    /** Offset of the first byte. */
+    pub start: u64,
+    /** Offset after the last byte. */
+    pub end: u64,
+}
+
+impl Span {
+    /** Get the line and column at which the span starts, both starting at 1.
+
+    The input has to be the one the span was recorded from. */
+    pub fn line_column(&self, input: &str) -> (u64, u64) {
+        let end = usize::try_from(self.start).map_or(input.len(), |start| start.min(input.len()));
+        let mut lines = Lines::new();
+        lines.advance(&input.as_bytes()[..end]);
+        lines.locate(self.start)
+    }
+}
+
+/** Spans of an attribute. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AttributeSpan {
+    /** Span of the attribute name. */
+    pub name: Span,
+    /** Span of the attribute value, excluding the quotes. */
+    pub value: Span,
+}
+
+/** Spans recorded for a parsed element. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ElementSpans {
+    pub(crate) start_tag: Span,
+    pub(crate) end_tag: Option<Span>,
+    pub(crate) attributes: Vec<(String, AttributeSpan)>,
+    /** Span of each child, in the same order as the children. */
+    pub(crate) children: Vec<Span>,
+}
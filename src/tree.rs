@@ -0,0 +1,225 @@
+use std::{iter::FusedIterator, mem, vec};
+
+use crate::{Document, Item};
+
+/** Handle of a node within a [`Tree`].
+
+Handles stay valid as long as the tree exists. Handles of one tree must not be used with another. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/** Items stored side by side with links between them, so that every node can reach its parent and siblings.
+
+A tree is built from the items returned by parsing or from a [`Document`], and can be turned back into items.
+Every item is a node. The children of elements are nodes of their own, so the elements stored in the tree have no children.
+```rust
+# use larix::*;
+let items = parse("<doc><section id=\"intro\"><p>Hello</p></section></doc>")?;
+let tree = Tree::from(items);
+
+// Which section is the paragraph in?
+let (p, _) = tree
+    .iter()
+    .find(|(_, item)| matches!(item, Item::Element(el) if el.name == "p"))
+    .unwrap();
+let section = tree
+    .ancestors(p)
+    .find_map(|id| match tree.get(id) {
+        Some(Item::Element(el)) if el.name == "section" => Some(el),
+        _ => None,
+    })
+    .unwrap();
+assert_eq!(section.attributes.get("id").map(String::as_str), Some("intro"));
+
+assert_eq!(stringify(&tree.into_items()), "<doc><section id=\"intro\"><p>Hello</p></section></doc>");
+# Ok::<(), Error>(())
+```*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tree {
+    /** All nodes in document order, indexed by their ID. */
+    nodes: Vec<Node>,
+    /** Nodes at the top level, in order. */
+    roots: Vec<NodeId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Node {
+    item: Item,
+    parent: Option<NodeId>,
+    /** Position among the children of the parent, or among the roots. */
+    index: usize,
+    children: Vec<NodeId>,
+}
+
+impl Tree {
+    /** Get the number of nodes. */
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /** Whether there are no nodes. */
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /** Get the nodes at the top level, in order. */
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /** Get the item of a node. Elements are returned without their children, see [`children`](Self::children). */
+    pub fn get(&self, id: NodeId) -> Option<&Item> {
+        Some(&self.nodes.get(id.0)?.item)
+    }
+
+    /** Get the item of a node for modification.
+
+    Children added to an element this way come before the children of its node when turning the tree back into items. */
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Item> {
+        Some(&mut self.nodes.get_mut(id.0)?.item)
+    }
+
+    /** Iterate over all nodes and their items in document order. */
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Item)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (NodeId(id), &node.item))
+    }
+
+    /** Get the parent of a node, or `None` if it is at the top level. */
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes.get(id.0)?.parent
+    }
+
+    /** Get the children of a node, in order. */
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.nodes.get(id.0).map_or(&[], |node| &node.children)
+    }
+
+    /** Iterate over the ancestors of a node, from its parent up to the top level. */
+    pub fn ancestors(&self, id: NodeId) -> Ancestors<'_> {
+        Ancestors {
+            tree: self,
+            next: self.parent(id),
+        }
+    }
+
+    /** Get the node following a node within the same parent. */
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let index = self.nodes.get(id.0)?.index.checked_add(1)?;
+        self.siblings(id).get(index).copied()
+    }
+
+    /** Get the node preceding a node within the same parent. */
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let index = self.nodes.get(id.0)?.index.checked_sub(1)?;
+        self.siblings(id).get(index).copied()
+    }
+
+    /** Get the node and its siblings, in order. */
+    fn siblings(&self, id: NodeId) -> &[NodeId] {
+        match self.parent(id) {
+            Some(parent) => self.children(parent),
+            None => &self.roots,
+        }
+    }
+
+    /** Turn the tree back into items, putting the children of each node back into its element. */
+    pub fn into_items(self) -> Vec<Item> {
+        // Children come after their parents, so going backwards builds every element after its children.
+        let mut built: Vec<Option<Item>> = Vec::with_capacity(self.nodes.len());
+        built.resize_with(self.nodes.len(), || None);
+
+        for (id, node) in self.nodes.into_iter().enumerate().rev() {
+            let mut item = node.item;
+            if let Item::Element(element) = &mut item {
+                let children = node
+                    .children
+                    .iter()
+                    .filter_map(|child| built.get_mut(child.0)?.take());
+                element.children.extend(children);
+            }
+            if let Some(slot) = built.get_mut(id) {
+                *slot = Some(item);
+            }
+        }
+
+        self.roots
+            .iter()
+            .filter_map(|root| built.get_mut(root.0)?.take())
+            .collect()
+    }
+}
+
+impl From<Vec<Item>> for Tree {
+    fn from(items: Vec<Item>) -> Self {
+        let mut tree = Tree {
+            nodes: Vec::new(),
+            roots: Vec::new(),
+        };
+
+        // Remaining items of each open element, with the node of the element.
+        let mut stack: Vec<(vec::IntoIter<Item>, Option<NodeId>)> = vec![(items.into_iter(), None)];
+        while let Some((items, parent)) = stack.last_mut() {
+            let parent = *parent;
+            let Some(mut item) = items.next() else {
+                stack.pop();
+                continue;
+            };
+
+            let id = NodeId(tree.nodes.len());
+            let siblings = match parent.and_then(|parent| tree.nodes.get_mut(parent.0)) {
+                Some(parent) => &mut parent.children,
+                None => &mut tree.roots,
+            };
+            let index = siblings.len();
+            siblings.push(id);
+
+            if let Item::Element(element) = &mut item {
+                stack.push((mem::take(&mut element.children).into_iter(), Some(id)));
+            }
+            tree.nodes.push(Node {
+                item,
+                parent,
+                index,
+                children: Vec::new(),
+            });
+        }
+
+        tree
+    }
+}
+
+impl From<Document> for Tree {
+    fn from(document: Document) -> Self {
+        Tree::from(document.into_items())
+    }
+}
+
+impl From<Tree> for Vec<Item> {
+    fn from(tree: Tree) -> Self {
+        tree.into_items()
+    }
+}
+
+/** Iterator over the ancestors of a node, from its parent up to the top level.
+
+Created by [`Tree::ancestors`]. */
+#[derive(Clone, Debug)]
+pub struct Ancestors<'a> {
+    tree: &'a Tree,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.tree.parent(id);
+        Some(id)
+    }
+}
+
+impl FusedIterator for Ancestors<'_> {}
@@ -1,234 +1,958 @@
-use std::{collections::HashMap, string::FromUtf8Error};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
 
 use quick_xml::{
     errors::IllFormedError,
-    events::{attributes::Attribute, BytesStart, Event},
+    events::{attributes::AttrError, BytesStart, Event},
     name::QName,
-    Reader,
+    Error as XmlError, Reader,
 };
 
-use crate::{Element, Error, Item};
+use crate::{
+    dtd::Dtd,
+    error::Cause,
+    escape::escape_text,
+    namespace::{duplicate_attribute, Scopes},
+    pi::is_space,
+    span::ElementSpans,
+    stringify_with, AttributeSpan, DocType, DocumentError, Element, Error, Item, Limit,
+    ParseOptions, Position, ProcessingInstruction, SerializeOptions, Span,
+};
 
 /** Stringifies a list of XML items into valid XML.
 
 Equivalent to calling `to_string` on each item and concatenating the results.
 */
-pub fn stringify(xml: &Vec<Item>) -> String {
-    let mut result = String::new();
-    for item in xml {
-        result.push_str(&item.to_string())
-    }
-    result
+pub fn stringify(xml: &[Item]) -> String {
+    stringify_with(xml, &SerializeOptions::new())
 }
 
-/** Parse XML. Text is trimmed. */
+/** Parse XML. Text is trimmed.
+
+Equivalent to [`parse_with`] using [`ParseOptions::trim_text`]. */
 pub fn parse_trimmed(value: &str) -> Result<Vec<Item>, Error> {
-    let events = get_all_events(value, true)?;
-    from_events(&(events.iter().collect::<Vec<&Event>>())[..])
+    parse_with(value, &ParseOptions::new().trim_text(true))
 }
 
 /** Parse XML. */
 pub fn parse(value: &str) -> Result<Vec<Item>, Error> {
-    let events = get_all_events(value, false)?;
-    from_events(&(events.iter().collect::<Vec<&Event>>())[..])
+    parse_with(value, &ParseOptions::new())
 }
 
-fn from_events(events: &[&Event]) -> Result<Vec<Item>, Error> {
-    let mut children: Vec<Item> = Vec::new();
+/** Parse XML using the given options.
+```rust
+# use larix::*;
+let xml = r#"<config>
+    <server port="80" />
+</config>"#;
 
-    let mut i = 0;
-    while i < events.len() {
-        match &events[i] {
-            Event::Text(e) => {
-                let str_res = u8_to_string(&e);
-                let Ok(str) = str_res else {
-                    return non_decodable(str_res);
+let items = parse_with(xml, &ParseOptions::new().spans(true))?;
+let Item::Element(config) = &items[0] else {
+    panic!();
+};
+let Item::Element(server) = &config.children[1] else {
+    panic!();
+};
+
+let port = server.attribute_span("port").unwrap();
+assert_eq!(port.name.line_column(xml), (2, 13));
+# Ok::<(), Error>(())
+```*/
+pub fn parse_with(value: &str, options: &ParseOptions) -> Result<Vec<Item>, Error> {
+    parse_with_warnings(value, options).map(|(items, _)| items)
+}
+
+/** Parse XML using the given options, also returning problems which were tolerated.
+
+Problems are only tolerated if the options allow it, e.g. through [`ParseOptions::lenient_attributes`].
+```rust
+# use larix::*;
+let xml = r#"<server port="80" port="8080" />"#;
+
+let options = ParseOptions::new().lenient_attributes(true);
+let (items, warnings) = parse_with_warnings(xml, &options)?;
+
+assert_eq!(items.len(), 1);
+assert!(matches!(&warnings[0], Error::Attribute { attribute, .. } if attribute == "port"));
+# Ok::<(), Error>(())
+```*/
+pub fn parse_with_warnings(
+    value: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<Item>, Vec<Error>), Error> {
+    parse_str(value, options, false).map(|parsed| (parsed.items, parsed.warnings))
+}
+
+/** Parse XML using the given options, also returning the span of each item at the top level.
+
+Spans of nested items are recorded too, as if enabled through [`ParseOptions::spans`].
+```rust
+# use larix::*;
+let xml = "hi<a>text</a><!-- end -->";
+
+let (items, spans) = parse_with_spans(xml, &ParseOptions::new())?;
+
+assert_eq!(spans.len(), items.len());
+assert_eq!(spans[0], Span { start: 0, end: 2 });
+assert_eq!(spans[2], Span { start: 13, end: 25 });
+# Ok::<(), Error>(())
+```*/
+pub fn parse_with_spans(
+    value: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<Item>, Vec<Span>), Error> {
+    let options = options.clone().spans(true);
+    parse_str(value, &options, false).map(|parsed| (parsed.items, parsed.spans))
+}
+
+/** Items parsed from the input, along with what was recorded about them. */
+pub(crate) struct Parsed {
+    pub(crate) items: Vec<Item>,
+    /** Span of each item at the top level, if spans are recorded. */
+    pub(crate) spans: Vec<Span>,
+    pub(crate) warnings: Vec<Error>,
+}
+
+/** Parse XML from a string, optionally requiring it to be a document with a single root element. */
+pub(crate) fn parse_str(
+    value: &str,
+    options: &ParseOptions,
+    document: bool,
+) -> Result<Parsed, Error> {
+    let mut reader = Reader::from_str(value);
+    reader.config_mut().trim_text(options.trim_text);
+
+    build(
+        StrSource {
+            reader,
+            input: value,
+            lines: Lines::new(),
+            bom: bom_len(value.as_bytes()),
+        },
+        options,
+        document,
+    )
+}
+
+/** Parse XML from a buffered reader.
+
+The input is read in chunks, so it never has to be held in memory as a whole.
+```rust
+# use larix::*;
+let xml = "<element><child /></element>".as_bytes();
+
+let items = parse_reader(xml)?;
+
+assert_eq!(items.len(), 1);
+# Ok::<(), Error>(())
+```*/
+pub fn parse_reader(value: impl BufRead) -> Result<Vec<Item>, Error> {
+    parse_reader_with(value, &ParseOptions::new())
+}
+
+/** Parse XML from a buffered reader using the given options. */
+pub fn parse_reader_with(value: impl BufRead, options: &ParseOptions) -> Result<Vec<Item>, Error> {
+    // Reading one byte past the limit reveals whether the input exceeds it.
+    let value = value.take(options.max_input_size.saturating_add(1));
+    let mut reader = Reader::from_reader(LineCounter::new(value));
+    reader.config_mut().trim_text(options.trim_text);
+    // The reader looks for a byte order mark in the same way, without consuming anything else.
+    let bom = bom_len(reader.get_mut().fill_buf()?);
+
+    build(
+        BufSource {
+            reader,
+            buf: Vec::new(),
+            bom,
+        },
+        options,
+        false,
+    )
+    .map(|parsed| parsed.items)
+}
+
+/** Parse XML from the file at the given path. */
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Item>, Error> {
+    let file = File::open(path)?;
+    parse_reader(BufReader::new(file))
+}
+
+fn build(mut source: impl Source, options: &ParseOptions, document: bool) -> Result<Parsed, Error> {
+    let mut builder = TreeBuilder::new(options, document);
+    let mut warnings = Vec::new();
+
+    loop {
+        let (event, span) = match source.read_event() {
+            Ok(read) => read,
+            Err(cause) => {
+                // The input may be cut off at the limit, which is the actual problem then.
+                let cause = if source.buffer_position() > options.max_input_size {
+                    Cause::Limit(Limit::InputSize)
+                } else {
+                    cause.into()
                 };
-                children.push(Item::Text(str));
+                let offset = source.error_position();
+                return Err(builder.error(cause, offset, source.locate(offset)));
+            }
+        };
+
+        if span.end > options.max_input_size {
+            let cause = Cause::Limit(Limit::InputSize);
+            return Err(builder.error(cause, span.start, source.locate(span.start)));
+        }
+        if let Event::Eof = event {
+            break;
+        }
+
+        if let Err(cause) = builder.handle_event(&event, span) {
+            let offset = cause.offset().unwrap_or(span.start);
+            return Err(builder.error(cause, offset, source.locate(offset)));
+        }
+
+        for cause in std::mem::take(&mut builder.warnings) {
+            let offset = cause.offset().unwrap_or(span.start);
+            warnings.push(builder.error(cause, offset, source.locate(offset)));
+        }
+        builder.starting = None;
+    }
+
+    match builder.finish() {
+        Ok((items, spans)) => Ok(Parsed {
+            items,
+            spans,
+            warnings,
+        }),
+        Err(cause) => {
+            let end = source.buffer_position();
+            Err(builder.error(cause, end, source.locate(end)))
+        }
+    }
+}
+
+/** Input from which XML events are read. */
+trait Source {
+    /** Read the next event, along with the span of input it was read from. */
+    fn read_event(&mut self) -> Result<(Event<'_>, Span), XmlError>;
+
+    /** Byte offset up to which the input has been read. */
+    fn buffer_position(&self) -> u64;
+
+    /** Byte offset of the markup which caused the last error. */
+    fn error_position(&self) -> u64;
+
+    /** Get the line and column of a byte offset. */
+    fn locate(&mut self, offset: u64) -> (u64, u64);
+}
+
+/** Get the length of the UTF-8 byte order mark at the start of the input, if any.
+
+The reader skips it without counting it, so it has to be added to the positions the reader reports. */
+fn bom_len(input: &[u8]) -> u64 {
+    if input.starts_with(b"\xEF\xBB\xBF") {
+        3
+    } else {
+        0
+    }
+}
+
+struct StrSource<'a> {
+    reader: Reader<&'a [u8]>,
+    input: &'a str,
+    /** Line starts in the input, as far as they have been needed. */
+    lines: Lines,
+    /** Length of the byte order mark. */
+    bom: u64,
+}
+
+impl Source for StrSource<'_> {
+    fn read_event(&mut self) -> Result<(Event<'_>, Span), XmlError> {
+        let start = self.bom + self.reader.buffer_position();
+        let event = self.reader.read_event()?;
+        let end = self.bom + self.reader.buffer_position();
+        Ok((event, Span { start, end }))
+    }
+
+    fn buffer_position(&self) -> u64 {
+        self.bom + self.reader.buffer_position()
+    }
+
+    fn error_position(&self) -> u64 {
+        self.bom + self.reader.error_position()
+    }
+
+    fn locate(&mut self, offset: u64) -> (u64, u64) {
+        let start = self.lines.consumed as usize;
+        let end =
+            usize::try_from(offset).map_or(self.input.len(), |offset| offset.min(self.input.len()));
+        if start < end {
+            self.lines.advance(&self.input.as_bytes()[start..end]);
+        }
+        self.lines.locate(offset)
+    }
+}
+
+struct BufSource<R> {
+    reader: Reader<LineCounter<R>>,
+    buf: Vec<u8>,
+    /** Length of the byte order mark. */
+    bom: u64,
+}
+
+impl<R: BufRead> Source for BufSource<R> {
+    fn read_event(&mut self) -> Result<(Event<'_>, Span), XmlError> {
+        // Errors and positions only ever refer to the event being read,
+        // so lines before it do not need to be remembered anymore.
+        let start = self.bom + self.reader.buffer_position();
+        self.reader.get_mut().lines.forget_before(start);
+
+        self.buf.clear();
+        let event = self.reader.read_event_into(&mut self.buf)?;
+        let end = self.bom + self.reader.buffer_position();
+        Ok((event, Span { start, end }))
+    }
+
+    fn buffer_position(&self) -> u64 {
+        self.bom + self.reader.buffer_position()
+    }
+
+    fn error_position(&self) -> u64 {
+        self.bom + self.reader.error_position()
+    }
+
+    fn locate(&mut self, offset: u64) -> (u64, u64) {
+        self.reader.get_ref().lines.locate(offset)
+    }
+}
+
+/** Wraps a buffered reader, keeping track of where lines start in the consumed input. */
+struct LineCounter<R> {
+    inner: R,
+    lines: Lines,
+}
+
+impl<R> LineCounter<R> {
+    fn new(inner: R) -> Self {
+        LineCounter {
+            inner,
+            lines: Lines::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Read for LineCounter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.lines.advance(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCounter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // The bytes being consumed have already been returned by `fill_buf`,
+        // so this does not read from the underlying source.
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.lines.advance(&buf[..amt.min(buf.len())]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/** Line starts within a contiguous stretch of input. */
+pub(crate) struct Lines {
+    /** Number of bytes seen so far. */
+    consumed: u64,
+    /** Number of lines which started before the first entry of `starts`. */
+    forgotten: u64,
+    /** Byte offsets at which lines start, in ascending order. */
+    starts: Vec<u64>,
+}
+
+impl Lines {
+    pub(crate) fn new() -> Self {
+        Lines {
+            consumed: 0,
+            forgotten: 0,
+            starts: vec![0],
+        }
+    }
+
+    pub(crate) fn advance(&mut self, bytes: &[u8]) {
+        for (i, byte) in bytes.iter().enumerate() {
+            if *byte == b'\n' {
+                self.starts.push(self.consumed + i as u64 + 1);
+            }
+        }
+        self.consumed += bytes.len() as u64;
+    }
+
+    /** Forget line starts which are not needed to locate offsets after the given one. */
+    fn forget_before(&mut self, offset: u64) {
+        let line = self.starts.partition_point(|start| *start <= offset);
+        if line > 1 {
+            self.starts.drain(..line - 1);
+            self.forgotten += line as u64 - 1;
+        }
+    }
+
+    pub(crate) fn locate(&self, offset: u64) -> (u64, u64) {
+        let line = self.starts.partition_point(|start| *start <= offset).max(1);
+        let column = offset.saturating_sub(self.starts[line - 1]) + 1;
+        (self.forgotten + line as u64, column)
+    }
+}
+
+/** Builds the item tree from a stream of events in a single pass.
+
+Elements which have been started but not yet ended are kept on a stack.
+Every other item is appended to the children of the innermost open element,
+or to the top level if no element is open.
+*/
+struct TreeBuilder<'o> {
+    /** Items at the top level. */
+    items: Vec<Item>,
+    /** Spans of the items at the top level, if spans are recorded. */
+    spans: Vec<Span>,
+    /** Currently open elements, innermost last. */
+    open: Vec<Element>,
+    /** Name of the element whose start tag is being read, so that problems within the tag are located at it,
+    and whether it has been added to its parent already. */
+    starting: Option<(String, bool)>,
+    /** Namespaces declared by the open elements, if namespaces are resolved. */
+    scopes: Option<Scopes>,
+    /** Problems which were tolerated, but not yet located in the input. */
+    warnings: Vec<Cause>,
+    /** Number of items started so far, at all depths. */
+    count: usize,
+    /** Whether the input must be a document with a single root element. */
+    document: bool,
+    /** Whether any event has been handled, so that the XML declaration can be checked to come first. */
+    started: bool,
+    /** Declarations from the internal subset of the DOCTYPE. */
+    dtd: Dtd,
+    /** Number of bytes inserted by entity references so far. */
+    expanded: usize,
+    options: &'o ParseOptions,
+}
+
+impl<'o> TreeBuilder<'o> {
+    fn new(options: &'o ParseOptions, document: bool) -> Self {
+        TreeBuilder {
+            items: Vec::new(),
+            spans: Vec::new(),
+            open: Vec::new(),
+            starting: None,
+            scopes: options.namespaces.then(Scopes::new),
+            warnings: Vec::new(),
+            count: 0,
+            document,
+            started: false,
+            dtd: Dtd::default(),
+            expanded: 0,
+            options,
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event, span: Span) -> Result<(), Cause> {
+        if self.document {
+            self.check_document(event)?;
+        }
+        self.started = true;
+
+        match event {
+            Event::Text(e) => {
+                let text = self.unescape(decode(e)?)?;
+                self.push_text(text, span)?;
             }
             Event::Comment(e) => {
-                let str_res = u8_to_string(&e);
-                let Ok(str) = str_res else {
-                    return non_decodable(str_res);
-                };
-                children.push(Item::Comment(str));
+                if !self.options.drop_comments {
+                    self.push(Item::Comment(decode(e)?), span)?;
+                }
             }
             Event::DocType(e) => {
-                let str_res = u8_to_string(&e);
-                let Ok(str) = str_res else {
-                    return non_decodable(str_res);
-                };
-                children.push(Item::DocType(str));
+                if self.options.reject_doctype {
+                    return Err(Cause::DocType);
+                }
+                let doctype: DocType = decode(e)?.parse().map_err(Cause::MalformedDocType)?;
+                if let Some(subset) = &doctype.internal_subset {
+                    self.dtd = Dtd::parse(subset).map_err(Cause::MalformedDocType)?;
+                }
+                self.push(Item::DocType(doctype), span)?;
             }
             Event::CData(e) => {
-                let str_res = u8_to_string(&e);
-                let Ok(str) = str_res else {
-                    return non_decodable(str_res);
-                };
-                children.push(Item::CData(str));
-            }
-            Event::Decl(e) => {
-                let str_res = u8_to_string(&e);
-                let Ok(str) = str_res else {
-                    return non_decodable(str_res);
-                };
-                children.push(Item::Decl(str));
+                let cdata = decode(e)?;
+                if self.options.merge_cdata {
+                    // Raw text is stored escaped, while CDATA never is.
+                    let text = if self.options.raw_values {
+                        escape_text(&cdata).into_owned()
+                    } else {
+                        cdata
+                    };
+                    self.push_text(text, span)?;
+                } else {
+                    self.check_text_length(cdata.len())?;
+                    self.push(Item::CData(cdata), span)?;
+                }
             }
+            Event::Decl(e) => self.push(Item::Decl(decode(e)?), span)?,
             Event::PI(e) => {
-                let str_res = u8_to_string(&e);
-                let Ok(str) = str_res else {
-                    return non_decodable(str_res);
-                };
-                children.push(Item::PI(str));
+                if !self.options.drop_processing_instructions {
+                    let target = decode(e.target())?;
+                    let data = decode(e.content())?;
+                    let data = data.trim_start_matches(is_space).to_owned();
+                    let pi = ProcessingInstruction::new(target, data).map_err(Cause::PI)?;
+                    self.push(Item::PI(pi), span)?;
+                }
             }
             Event::Empty(e) => {
-                let name_res = get_name(e);
-                let Ok(name) = name_res else {
-                    return non_decodable(name_res);
-                };
-                let attr_res = get_attributes(e);
-                let Ok(attributes) = attr_res else {
-                    return non_decodable(attr_res);
-                };
-
-                children.push(Item::Element(Element {
-                    name,
-                    attributes,
-                    self_closing: true,
-                    children: Vec::new(),
-                }))
+                let element = self.start_element(e, span, true)?;
+                if let Some(scopes) = &mut self.scopes {
+                    scopes.leave();
+                }
+                self.push(Item::Element(element), span)?;
+                if let Some((_, added)) = &mut self.starting {
+                    *added = true;
+                }
             }
             Event::Start(e) => {
-                let name_res = get_name(e);
-                let Ok(name) = name_res else {
-                    return non_decodable(name_res);
-                };
-                let attr_res = get_attributes(e);
-                let Ok(attributes) = attr_res else {
-                    return non_decodable(attr_res);
+                let element = self.start_element(e, span, false)?;
+                self.open.push(element);
+                // The element is part of the path through the open elements now.
+                self.starting = None;
+            }
+            Event::End(e) => {
+                let end_name = qname_to_string(&e.name())?;
+                let Some(mut element) = self.open.pop() else {
+                    return Err(
+                        XmlError::IllFormed(IllFormedError::UnmatchedEndTag(end_name)).into(),
+                    );
                 };
+                if element.name != end_name {
+                    return Err(XmlError::IllFormed(IllFormedError::MismatchedEndTag {
+                        expected: std::mem::take(&mut element.name),
+                        found: end_name,
+                    })
+                    .into());
+                }
+                if let Some(scopes) = &mut self.scopes {
+                    scopes.leave();
+                }
+                if let Some(spans) = &mut element.spans {
+                    spans.end_tag = Some(span);
+                }
+                let span = element.span().unwrap_or(span);
+                self.push(Item::Element(element), span)?;
+            }
+            // The end of input is handled by the caller.
+            Event::Eof => (),
+        }
 
-                let mut nested_events = Vec::new();
-                let mut names = vec![name.clone()];
-
-                loop {
-                    i += 1;
-
-                    match events[i] {
-                        Event::Start(e) => {
-                            let name_res = get_name(e);
-                            let Ok(name) = name_res else {
-                                return non_decodable(name_res);
-                            };
-                            names.push(name);
-                        }
-                        Event::End(e) => {
-                            let end_name_res = qname_to_string(&e.name());
-                            let Ok(end_name) = end_name_res else {
-                                return non_decodable(end_name_res);
-                            };
-                            let Some(start_name) = names.pop() else {
-                                return Err(Error::IllFormed(IllFormedError::UnmatchedEndTag(
-                                    end_name,
-                                )));
-                            };
-                            if start_name != end_name {
-                                return Err(Error::IllFormed(IllFormedError::MismatchedEndTag {
-                                    expected: start_name,
-                                    found: end_name,
-                                }));
-                            }
-                        }
-                        _ => (),
-                    }
+        Ok(())
+    }
+
+    fn start_element(
+        &mut self,
+        start: &BytesStart,
+        span: Span,
+        self_closing: bool,
+    ) -> Result<Element, Cause> {
+        let mut element = Element::new(get_name(start)?);
+        element.self_closing = self_closing;
+        // The path is only worked out if there is a problem, as that takes a look at all siblings.
+        self.starting = Some((element.name.clone(), false));
 
-                    if names.is_empty() {
-                        break;
+        if self.open.len() >= self.options.max_depth {
+            return Err(Cause::Limit(Limit::Depth));
+        }
+        self.count_item()?;
+
+        // Attribute positions are relative to the tag content, which follows the `<`.
+        let offset = span.start + 1;
+        let mut spans = Vec::new();
+
+        for attr in start.attributes() {
+            let attr = match attr {
+                Ok(attr) => attr,
+                Err(cause) => {
+                    let cause = attribute_error(start, &element.name, cause, offset);
+                    if self.options.lenient_attributes {
+                        self.warnings.push(cause);
+                        continue;
                     }
+                    return Err(cause);
+                }
+            };
+
+            let key = qname_to_string(&attr.key)?;
+            if self.options.spans {
+                spans.push((
+                    key.clone(),
+                    AttributeSpan {
+                        name: span_within(start, attr.key.as_ref(), offset),
+                        value: span_within(start, &attr.value, offset),
+                    },
+                ));
+            }
+            let value = self.unescape(decode(&attr.value)?)?;
+            self.check_attribute(&element, &key, &value)?;
+            element.attributes.insert(key, value);
+        }
+
+        let defaults: Vec<(String, String)> = self
+            .dtd
+            .defaults(&element.name)
+            .iter()
+            .filter(|(name, _)| !element.attributes.contains_key(name))
+            .cloned()
+            .collect();
+        for (name, value) in defaults {
+            let value = self.unescape(value)?;
+            self.check_attribute(&element, &name, &value)?;
+            element.attributes.insert(name, value);
+        }
 
-                    nested_events.push(events[i]);
+        if let Some(scopes) = &mut self.scopes {
+            element.namespace = scopes.enter(&element.name, &mut element.attributes)?;
+
+            while let Some((earlier, duplicate)) = duplicate_attribute(&element.attributes) {
+                let cause = duplicate_error(start, &element.name, &earlier, &duplicate, offset);
+                if !self.options.lenient_attributes {
+                    return Err(cause);
                 }
-                let el_children = from_events(&nested_events[..])?;
-                children.push(Item::Element(Element {
-                    name,
-                    attributes,
-                    self_closing: false,
-                    children: el_children,
-                }));
+                self.warnings.push(cause);
+                element.attributes.remove(&duplicate);
+                spans.retain(|(name, _)| *name != duplicate);
             }
-            Event::End(e) => {
-                // Should have been handled in Event::Start block. It's therefore an umatched end.
-                let name_res = qname_to_string(&e.name());
-                let Ok(name) = name_res else {
-                    return non_decodable(name_res);
-                };
-                return Err(Error::IllFormed(IllFormedError::UnmatchedEndTag(name)));
+        }
+
+        if self.options.spans {
+            element.spans = Some(Box::new(ElementSpans {
+                start_tag: span,
+                end_tag: None,
+                attributes: spans,
+                children: Vec::new(),
+            }));
+        }
+
+        Ok(element)
+    }
+
+    /** Decode entity and character references, unless raw values are to be kept. */
+    fn unescape(&mut self, raw: String) -> Result<String, Cause> {
+        if self.options.raw_values {
+            return Ok(raw);
+        }
+
+        match self.dtd.expand(&raw, self.options, &mut self.expanded)? {
+            Cow::Borrowed(_) => Ok(raw),
+            Cow::Owned(unescaped) => Ok(unescaped),
+        }
+    }
+
+    /** Add text, merging it with preceding text if CDATA is merged into text. */
+    fn push_text(&mut self, text: String, span: Span) -> Result<(), Cause> {
+        if self.options.merge_cdata {
+            let (siblings, spans) = match self.open.last_mut() {
+                Some(parent) => (
+                    &mut parent.children,
+                    parent.spans.as_mut().map(|spans| &mut spans.children),
+                ),
+                None => (&mut self.items, Some(&mut self.spans)),
+            };
+            if let Some(Item::Text(previous)) = siblings.last_mut() {
+                if previous.len().saturating_add(text.len()) > self.options.max_text_length {
+                    return Err(Cause::Limit(Limit::TextLength));
+                }
+                previous.push_str(&text);
+                if let Some(last) = spans.and_then(|spans| spans.last_mut()) {
+                    last.end = span.end;
+                }
+                return Ok(());
             }
-            Event::Eof => {
-                // Should have been filtered by get_all_events
-                panic!("Internal Error: EoF found where EoF should never be.");
+        }
+
+        self.check_text_length(text.len())?;
+        self.push(Item::Text(text), span)
+    }
+
+    /** Check that an attribute can be added to an element without exceeding the limits. */
+    fn check_attribute(&self, element: &Element, name: &str, value: &str) -> Result<(), Cause> {
+        if !element.attributes.contains_key(name)
+            && element.attributes.len() >= self.options.max_attributes
+        {
+            return Err(Cause::Limit(Limit::Attributes));
+        }
+        if value.len() > self.options.max_attribute_length {
+            return Err(Cause::Limit(Limit::AttributeLength));
+        }
+        Ok(())
+    }
+
+    fn check_text_length(&self, length: usize) -> Result<(), Cause> {
+        if length > self.options.max_text_length {
+            return Err(Cause::Limit(Limit::TextLength));
+        }
+        Ok(())
+    }
+
+    /** Count an item towards the limit of items. */
+    fn count_item(&mut self) -> Result<(), Cause> {
+        self.count += 1;
+        if self.count > self.options.max_items {
+            return Err(Cause::Limit(Limit::Items));
+        }
+        Ok(())
+    }
+
+    /** Check that an event fits the structure of a document. Within the root element, only its place matters to the XML declaration. */
+    fn check_document(&self, event: &Event) -> Result<(), Cause> {
+        if let Event::Decl(_) = event {
+            if self.started {
+                return Err(Cause::Document(DocumentError::MisplacedDecl));
+            }
+        }
+        if !self.open.is_empty() {
+            return Ok(());
+        }
+
+        let has_root = self
+            .items
+            .iter()
+            .any(|item| matches!(item, Item::Element(_)));
+
+        match event {
+            Event::DocType(_) if has_root => Err(Cause::Document(DocumentError::MisplacedDocType)),
+            Event::DocType(_)
+                if self
+                    .items
+                    .iter()
+                    .any(|item| matches!(item, Item::DocType(_))) =>
+            {
+                Err(Cause::Document(DocumentError::MultipleDocTypes))
+            }
+            Event::Start(_) | Event::Empty(_) if has_root => {
+                Err(Cause::Document(DocumentError::MultipleRoots))
+            }
+            Event::Text(e) if !e.iter().all(u8::is_ascii_whitespace) => {
+                Err(Cause::Document(DocumentError::TextOutsideRoot))
             }
+            Event::CData(_) => Err(Cause::Document(DocumentError::TextOutsideRoot)),
+            _ => Ok(()),
         }
-        i += 1;
     }
 
-    Ok(children)
-}
+    /** Add an item to the innermost open element. Elements have already been counted when they were started. */
+    fn push(&mut self, item: Item, span: Span) -> Result<(), Cause> {
+        if !matches!(item, Item::Element(_)) {
+            self.count_item()?;
+        }
 
-fn qname_to_string(qname: &QName) -> Result<String, FromUtf8Error> {
-    u8_to_string(qname.as_ref())
-}
+        match self.open.last_mut() {
+            Some(parent) => {
+                if let Some(spans) = &mut parent.spans {
+                    spans.children.push(span);
+                }
+                parent.children.push(item)
+            }
+            None => {
+                if self.options.spans {
+                    self.spans.push(span);
+                }
+                self.items.push(item)
+            }
+        }
+        Ok(())
+    }
 
-fn u8_to_string(u8: &[u8]) -> Result<String, FromUtf8Error> {
-    String::from_utf8(u8.to_vec())
-}
+    fn finish(&mut self) -> Result<(Vec<Item>, Vec<Span>), Cause> {
+        if let Some(element) = self.open.last() {
+            return Err(
+                XmlError::IllFormed(IllFormedError::MissingEndTag(element.name.clone())).into(),
+            );
+        }
+        if self.document
+            && !self
+                .items
+                .iter()
+                .any(|item| matches!(item, Item::Element(_)))
+        {
+            return Err(Cause::Document(DocumentError::MissingRoot));
+        }
+
+        Ok((
+            std::mem::take(&mut self.items),
+            std::mem::take(&mut self.spans),
+        ))
+    }
+
+    /** Attach the position in the input and in the tree to an error. */
+    fn error(&self, cause: Cause, offset: u64, (line, column): (u64, u64)) -> Error {
+        cause.at(Position {
+            offset,
+            line,
+            column,
+            path: self.path(),
+        })
+    }
 
-fn get_all_events(xml: &str, trim: bool) -> Result<Vec<Event>, Error> {
-    let mut events = Vec::new();
+    /** Get the path of the innermost open element, or of the element whose start tag is being read. */
+    fn path(&self) -> String {
+        let mut path = String::new();
+        let mut siblings = &self.items;
 
-    let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(trim);
+        for element in &self.open {
+            path.push_str(&path_segment(siblings, &element.name));
+            siblings = &element.children;
+        }
+        if let Some((name, added)) = &self.starting {
+            let siblings = if *added {
+                &siblings[..siblings.len().saturating_sub(1)]
+            } else {
+                siblings
+            };
+            path.push_str(&path_segment(siblings, name));
+        }
 
-    loop {
-        match reader.read_event() {
-            Err(err) => return Err(err),
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+}
 
-            Ok(Event::Eof) => break,
+/** Get the path segment of an element which comes after the given siblings, e.g. ```/server[3]```. */
+fn path_segment(siblings: &[Item], name: &str) -> String {
+    let index = siblings
+        .iter()
+        .filter(|item| matches!(item, Item::Element(el) if el.name == name))
+        .count()
+        + 1;
 
-            Ok(e) => events.push(e),
-        };
+    if index > 1 {
+        format!("/{name}[{index}]")
+    } else {
+        format!("/{name}")
     }
+}
 
-    Ok(events)
+fn qname_to_string(qname: &QName) -> Result<String, XmlError> {
+    decode(qname.as_ref())
 }
 
-fn non_decodable<T, U>(res: Result<T, FromUtf8Error>) -> Result<U, Error> {
-    Err(Error::NonDecodable(Some(res.err().unwrap().utf8_error())))
+fn decode(u8: &[u8]) -> Result<String, XmlError> {
+    String::from_utf8(u8.to_vec()).map_err(|err| XmlError::NonDecodable(Some(err.utf8_error())))
 }
 
-fn get_name(start: &BytesStart) -> Result<String, FromUtf8Error> {
+fn get_name(start: &BytesStart) -> Result<String, XmlError> {
     qname_to_string(&start.name())
 }
 
-fn get_attributes(start: &BytesStart) -> Result<HashMap<String, String>, FromUtf8Error> {
-    let attrs: Vec<Attribute> = start
-        .attributes()
-        .filter_map(|attr| {
-            if attr.is_ok() {
-                Some(attr.unwrap())
-            } else {
-                None
-            }
-        })
-        .collect();
+/** Get the span of a slice of the tag content, given the offset of the tag content in the input. */
+fn span_within(start: &BytesStart, bytes: &[u8], offset: u64) -> Span {
+    let within = (bytes.as_ptr() as usize).saturating_sub(start.as_ptr() as usize);
+    let start = offset + within as u64;
+    Span {
+        start,
+        end: start + bytes.len() as u64,
+    }
+}
+
+/** Describe a malformed attribute, given the offset of the tag content in the input. */
+fn attribute_error(start: &BytesStart, element: &str, cause: AttrError, offset: u64) -> Cause {
+    let content: &[u8] = start;
+
+    let (position, attribute) = match cause {
+        AttrError::ExpectedEq(pos) => (pos, name_before(content, pos)),
+        AttrError::ExpectedValue(pos) | AttrError::UnquotedValue(pos) => {
+            (pos, name_before_eq(content, pos))
+        }
+        AttrError::ExpectedQuote(pos, quote) => {
+            // The opening quote has no closing counterpart, so it is the last one in the tag.
+            let opening = content
+                .iter()
+                .rposition(|byte| *byte == quote)
+                .unwrap_or(pos);
+            (opening, name_before_eq(content, opening))
+        }
+        AttrError::Duplicated(pos, _) => (pos, name_after(content, pos)),
+    };
+
+    Cause::Attribute {
+        element: element.to_owned(),
+        attribute,
+        cause,
+        offset: offset + position as u64,
+    }
+}
 
-    let mut attributes = HashMap::with_capacity(attrs.len());
+/** Describe an attribute with the same expanded name as an earlier one, given the offset of the tag content in the input. */
+fn duplicate_error(
+    start: &BytesStart,
+    element: &str,
+    earlier: &str,
+    duplicate: &str,
+    offset: u64,
+) -> Cause {
+    // Attributes added from the DTD are not in the tag, so they are located at its start.
+    let position = |name: &str| {
+        start
+            .attributes()
+            .with_checks(false)
+            .flatten()
+            .find(|attr| attr.key.as_ref() == name.as_bytes())
+            .map_or(0, |attr| {
+                span_within(start, attr.key.as_ref(), 0).start as usize
+            })
+    };
+    let position = (position(duplicate), position(earlier));
 
-    for attr in attrs {
-        let key = qname_to_string(&attr.key)?;
-        let value = String::from_utf8((*attr.value).to_vec())?;
-        attributes.insert(key, value);
+    Cause::Attribute {
+        element: element.to_owned(),
+        attribute: duplicate.to_owned(),
+        cause: AttrError::Duplicated(position.0, position.1),
+        offset: offset + position.0 as u64,
     }
+}
+
+fn is_name_end(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || matches!(byte, b'=' | b'/' | b'>' | b'"' | b'\'')
+}
+
+/** Get the name starting at the given position. */
+fn name_after(content: &[u8], pos: usize) -> String {
+    let rest = content.get(pos..).unwrap_or_default();
+    let end = rest
+        .iter()
+        .position(|byte| is_name_end(*byte))
+        .unwrap_or(rest.len());
+    String::from_utf8_lossy(&rest[..end]).into_owned()
+}
+
+/** Get the name which ends before the given position, ignoring whitespace in between. */
+fn name_before(content: &[u8], pos: usize) -> String {
+    let before = &content[..pos.min(content.len())];
+    let end = before
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    let start = before[..end]
+        .iter()
+        .rposition(|byte| is_name_end(*byte))
+        .map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&before[start..end]).into_owned()
+}
 
-    Ok(attributes)
+/** Get the name which precedes the ```=``` before the given position. */
+fn name_before_eq(content: &[u8], pos: usize) -> String {
+    let before = &content[..pos.min(content.len())];
+    let eq = before.iter().rposition(|byte| *byte == b'=').unwrap_or(0);
+    name_before(content, eq)
 }
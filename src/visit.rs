@@ -0,0 +1,377 @@
+use std::{mem, slice, vec};
+
+use crate::{DocType, Element, Item, ProcessingInstruction};
+
+/** What to do after a node has been visited. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Visit {
+    /** Go on with the next node. */
+    Continue,
+    /** Do not visit the children of the element just entered. Elsewhere the same as [`Visit::Continue`]. */
+    Skip,
+    /** End the walk without visiting any further nodes. */
+    Stop,
+}
+
+/** What to do with a node after it has been visited for modification. */
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Edit {
+    /** Keep the node and go on with the next one. */
+    Continue,
+    /** Keep the node, but do not visit the children of the element just entered. Elsewhere the same as [`Edit::Continue`]. */
+    Skip,
+    /** Keep the node and end the walk without visiting any further nodes. */
+    Stop,
+    /** Put the given item in place of the node. The replacement is not visited. */
+    Replace(Item),
+    /** Remove the node, along with its children. */
+    Remove,
+}
+
+/** Callbacks for walking a tree with [`walk`].
+
+Elements are entered before and left after their children. The other items have no children, so they are only visited.
+Every callback does nothing by default, so only the relevant ones have to be implemented.
+```rust
+# use larix::*;
+struct Outline(Vec<String>);
+
+impl Visitor for Outline {
+    fn enter_element(&mut self, element: &Element) -> Visit {
+        self.0.push(element.name.clone());
+        // Do not descend into the head.
+        if element.name == "head" {
+            Visit::Skip
+        } else {
+            Visit::Continue
+        }
+    }
+}
+
+let items = parse("<html><head><title /></head><body><p /></body></html>")?;
+let mut outline = Outline(Vec::new());
+walk(&items, &mut outline);
+
+assert_eq!(outline.0, ["html", "head", "body", "p"]);
+# Ok::<(), Error>(())
+```*/
+pub trait Visitor {
+    /** Called for an element before its children. Returning [`Visit::Skip`] leaves them out. */
+    fn enter_element(&mut self, _element: &Element) -> Visit {
+        Visit::Continue
+    }
+
+    /** Called for an element after its children, even if they were skipped. */
+    fn leave_element(&mut self, _element: &Element) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_text(&mut self, _text: &str) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_cdata(&mut self, _cdata: &str) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_comment(&mut self, _comment: &str) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_pi(&mut self, _pi: &ProcessingInstruction) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_decl(&mut self, _decl: &str) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_doctype(&mut self, _doctype: &DocType) -> Visit {
+        Visit::Continue
+    }
+}
+
+/** Callbacks for modifying a tree in place with [`walk_mut`].
+
+Nodes are visited in the same order as by a [`Visitor`]. Each callback may change its node,
+or replace or remove it through the returned [`Edit`].
+```rust
+# use larix::*;
+struct Redact;
+
+impl VisitorMut for Redact {
+    fn enter_element(&mut self, element: &mut Element) -> Edit {
+        match element.name.as_str() {
+            "password" => Edit::Remove,
+            "name" => Edit::Replace(Item::Text(String::from("anonymous"))),
+            _ => Edit::Continue,
+        }
+    }
+
+    fn visit_comment(&mut self, _comment: &mut String) -> Edit {
+        Edit::Remove
+    }
+}
+
+let mut items = parse("<user><!-- internal --><name>Alice</name><password>secret</password></user>")?;
+walk_mut(&mut items, &mut Redact);
+
+assert_eq!(stringify(&items), "<user>anonymous</user>");
+# Ok::<(), Error>(())
+```*/
+pub trait VisitorMut {
+    /** Called for an element before its children. Returning [`Edit::Skip`] leaves them out. */
+    fn enter_element(&mut self, _element: &mut Element) -> Edit {
+        Edit::Continue
+    }
+
+    /** Called for an element after its children, even if they were skipped. */
+    fn leave_element(&mut self, _element: &mut Element) -> Edit {
+        Edit::Continue
+    }
+
+    fn visit_text(&mut self, _text: &mut String) -> Edit {
+        Edit::Continue
+    }
+
+    fn visit_cdata(&mut self, _cdata: &mut String) -> Edit {
+        Edit::Continue
+    }
+
+    fn visit_comment(&mut self, _comment: &mut String) -> Edit {
+        Edit::Continue
+    }
+
+    fn visit_pi(&mut self, _pi: &mut ProcessingInstruction) -> Edit {
+        Edit::Continue
+    }
+
+    fn visit_decl(&mut self, _decl: &mut String) -> Edit {
+        Edit::Continue
+    }
+
+    fn visit_doctype(&mut self, _doctype: &mut DocType) -> Edit {
+        Edit::Continue
+    }
+}
+
+/** Callbacks for building a new tree out of an old one with [`fold`].
+
+Each node is handed over and turned into the item taking its place, or into `None` to remove it.
+Elements are folded after their children, which have already been replaced by their results.
+```rust
+# use larix::*;
+struct Flatten;
+
+impl Fold for Flatten {
+    // Turn every element into its text content.
+    fn fold_element(&mut self, element: Element) -> Option<Item> {
+        Some(Item::Text(element.get_text_content()))
+    }
+
+    fn fold_cdata(&mut self, cdata: String) -> Option<Item> {
+        Some(Item::Text(cdata))
+    }
+}
+
+let items = parse("<p>Hello <b>World</b><![CDATA[!]]></p>")?;
+let folded = fold(items, &mut Flatten);
+
+assert_eq!(stringify(&folded), "Hello World!");
+# Ok::<(), Error>(())
+```*/
+pub trait Fold {
+    /** Called for an element before its children are folded.
+
+    Returning [`Visit::Skip`] keeps the children as they are, [`Visit::Stop`] keeps everything not yet folded as it is. */
+    fn enter_element(&mut self, _element: &Element) -> Visit {
+        Visit::Continue
+    }
+
+    /** Called for an element after its children have been folded, even if they were skipped. */
+    fn fold_element(&mut self, element: Element) -> Option<Item> {
+        Some(Item::Element(element))
+    }
+
+    fn fold_text(&mut self, text: String) -> Option<Item> {
+        Some(Item::Text(text))
+    }
+
+    fn fold_cdata(&mut self, cdata: String) -> Option<Item> {
+        Some(Item::CData(cdata))
+    }
+
+    fn fold_comment(&mut self, comment: String) -> Option<Item> {
+        Some(Item::Comment(comment))
+    }
+
+    fn fold_pi(&mut self, pi: ProcessingInstruction) -> Option<Item> {
+        Some(Item::PI(pi))
+    }
+
+    fn fold_decl(&mut self, decl: String) -> Option<Item> {
+        Some(Item::Decl(decl))
+    }
+
+    fn fold_doctype(&mut self, doctype: DocType) -> Option<Item> {
+        Some(Item::DocType(doctype))
+    }
+}
+
+/** Visit the items and everything within them in document order. */
+pub fn walk(items: &[Item], visitor: &mut (impl Visitor + ?Sized)) {
+    // Remaining children of each open element, with the element to leave once they are done.
+    let mut stack: Vec<(slice::Iter<Item>, Option<&Element>)> = vec![(items.iter(), None)];
+
+    while let Some((children, _)) = stack.last_mut() {
+        let visit = match children.next() {
+            Some(Item::Element(element)) => match visitor.enter_element(element) {
+                Visit::Continue => {
+                    stack.push((element.children.iter(), Some(element)));
+                    Visit::Continue
+                }
+                Visit::Skip => visitor.leave_element(element),
+                Visit::Stop => Visit::Stop,
+            },
+            Some(Item::Text(text)) => visitor.visit_text(text),
+            Some(Item::CData(cdata)) => visitor.visit_cdata(cdata),
+            Some(Item::Comment(comment)) => visitor.visit_comment(comment),
+            Some(Item::PI(pi)) => visitor.visit_pi(pi),
+            Some(Item::Decl(decl)) => visitor.visit_decl(decl),
+            Some(Item::DocType(doctype)) => visitor.visit_doctype(doctype),
+            None => match stack.pop() {
+                Some((_, Some(element))) => visitor.leave_element(element),
+                _ => Visit::Continue,
+            },
+        };
+        if visit == Visit::Stop {
+            return;
+        }
+    }
+}
+
+/** Visit the items and everything within them in document order, applying the edits of the visitor. */
+pub fn walk_mut(items: &mut Vec<Item>, visitor: &mut (impl VisitorMut + ?Sized)) {
+    // Items are moved out of the tree and back in once visited, so that they can be replaced and removed.
+    let mut remaining = mem::take(items).into_iter();
+    let mut done = Vec::with_capacity(remaining.len());
+    let mut stack: Vec<(Element, vec::IntoIter<Item>, Vec<Item>)> = Vec::new();
+    let mut stopped = false;
+
+    loop {
+        let next = if stopped { None } else { remaining.next() };
+        match next {
+            Some(Item::Element(mut element)) => match visitor.enter_element(&mut element) {
+                Edit::Continue => {
+                    let children = mem::take(&mut element.children).into_iter();
+                    let siblings = mem::replace(&mut remaining, children);
+                    let capacity = remaining.len();
+                    let visited = mem::replace(&mut done, Vec::with_capacity(capacity));
+                    stack.push((element, siblings, visited));
+                }
+                Edit::Skip => {
+                    let edit = visitor.leave_element(&mut element);
+                    apply(&mut done, Item::Element(element), edit, &mut stopped);
+                }
+                edit => apply(&mut done, Item::Element(element), edit, &mut stopped),
+            },
+            Some(mut item) => {
+                let edit = match &mut item {
+                    Item::Element(_) => Edit::Continue,
+                    Item::Text(text) => visitor.visit_text(text),
+                    Item::CData(cdata) => visitor.visit_cdata(cdata),
+                    Item::Comment(comment) => visitor.visit_comment(comment),
+                    Item::PI(pi) => visitor.visit_pi(pi),
+                    Item::Decl(decl) => visitor.visit_decl(decl),
+                    Item::DocType(doctype) => visitor.visit_doctype(doctype),
+                };
+                apply(&mut done, item, edit, &mut stopped);
+            }
+            None => {
+                // After stopping, whatever has not been visited is kept as it is.
+                done.extend(&mut remaining);
+                match stack.pop() {
+                    Some((mut element, siblings, visited)) => {
+                        element.children = mem::replace(&mut done, visited);
+                        remaining = siblings;
+                        let edit = if stopped {
+                            Edit::Continue
+                        } else {
+                            visitor.leave_element(&mut element)
+                        };
+                        apply(&mut done, Item::Element(element), edit, &mut stopped);
+                    }
+                    None => {
+                        *items = done;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/** Put a visited item back, or its replacement, as the edit demands. */
+fn apply(done: &mut Vec<Item>, item: Item, edit: Edit, stopped: &mut bool) {
+    match edit {
+        Edit::Continue | Edit::Skip => done.push(item),
+        Edit::Stop => {
+            done.push(item);
+            *stopped = true;
+        }
+        Edit::Replace(replacement) => done.push(replacement),
+        Edit::Remove => (),
+    }
+}
+
+/** Build new items by folding the items and everything within them, children before their parents. */
+pub fn fold(items: Vec<Item>, folder: &mut (impl Fold + ?Sized)) -> Vec<Item> {
+    let mut remaining = items.into_iter();
+    let mut done = Vec::with_capacity(remaining.len());
+    let mut stack: Vec<(Element, vec::IntoIter<Item>, Vec<Item>)> = Vec::new();
+    let mut stopped = false;
+
+    loop {
+        let next = if stopped { None } else { remaining.next() };
+        let folded = match next {
+            Some(Item::Element(mut element)) => match folder.enter_element(&element) {
+                Visit::Continue => {
+                    let children = mem::take(&mut element.children).into_iter();
+                    let siblings = mem::replace(&mut remaining, children);
+                    let capacity = remaining.len();
+                    let folded = mem::replace(&mut done, Vec::with_capacity(capacity));
+                    stack.push((element, siblings, folded));
+                    None
+                }
+                Visit::Skip => folder.fold_element(element),
+                Visit::Stop => {
+                    stopped = true;
+                    Some(Item::Element(element))
+                }
+            },
+            Some(Item::Text(text)) => folder.fold_text(text),
+            Some(Item::CData(cdata)) => folder.fold_cdata(cdata),
+            Some(Item::Comment(comment)) => folder.fold_comment(comment),
+            Some(Item::PI(pi)) => folder.fold_pi(pi),
+            Some(Item::Decl(decl)) => folder.fold_decl(decl),
+            Some(Item::DocType(doctype)) => folder.fold_doctype(doctype),
+            None => {
+                // After stopping, whatever has not been folded is kept as it is.
+                done.extend(&mut remaining);
+                match stack.pop() {
+                    Some((mut element, siblings, folded)) => {
+                        element.children = mem::replace(&mut done, folded);
+                        remaining = siblings;
+                        if stopped {
+                            Some(Item::Element(element))
+                        } else {
+                            folder.fold_element(element)
+                        }
+                    }
+                    None => return done,
+                }
+            }
+        };
+        done.extend(folded);
+    }
+}
@@ -1,6 +1,18 @@
 #[cfg(test)]
 mod tests {
-    use larix::{parse, Item};
+    use larix::{
+        fold, parse, parse_document, parse_document_with, parse_file, parse_reader,
+        parse_reader_with, parse_trimmed, parse_with, parse_with_spans, parse_with_warnings,
+        stringify, stringify_with, walk, walk_mut, write_items, DeclError, DocType, DocTypeError,
+        Document, DocumentError, Edit, Element, Error, ExternalId, Fold, Item, Limit, NodeId,
+        NodePath, PIError, ParseOptions, ProcessingInstruction, SerializeOptions, Span, Tree,
+        Visit, Visitor, VisitorMut, XmlDecl, XMLNS_NAMESPACE, XML_NAMESPACE,
+    };
+    use quick_xml::errors::IllFormedError;
+    use std::{
+        collections::{hash_map::DefaultHasher, HashSet},
+        hash::{Hash, Hasher},
+    };
 
     #[test]
     fn test_text() {
@@ -42,6 +54,16 @@ mod tests {
         };
         assert_eq!(items[0].to_string(), RAW);
         assert_eq!(inner, " abcxyz ");
+
+        // Comments cannot end early or contain "--".
+        let injected = Item::Comment(String::from(" x --><evil/><!-- "));
+        assert_eq!(injected.to_string(), "<!-- x - -><evil/><!- - -->");
+        let items = parse(&injected.to_string()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            Item::Comment(String::from("a---")).to_string(),
+            "<!--a- - - -->"
+        );
     }
 
     #[test]
@@ -52,17 +74,94 @@ mod tests {
 
         let items = parse(RAW).unwrap();
         assert_eq!(items.len(), 1);
-        let inner: &String = match &items[0] {
+        let inner: &DocType = match &items[0] {
             Item::DocType(e) => e,
             _ => panic!("Item is of wrong type."),
         };
+        assert_eq!(
+            items[0].to_string(),
+            r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd">"#
+        );
+        assert_eq!(inner.name, "html");
+        assert_eq!(inner.public_id(), Some("-//W3C//DTD XHTML 1.0 Strict//EN"));
+        assert_eq!(
+            inner.system_id(),
+            Some("http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd")
+        );
+        assert_eq!(inner.internal_subset, None);
+    }
+
+    #[test]
+    fn test_doctype_structure() {
+        const RAW: &str = r#"<!DOCTYPE note SYSTEM 'say "hi".dtd' [
+  <!ENTITY writer "Donald">
+  <!-- ] -->
+]>"#;
+
+        let items = parse(RAW).unwrap();
+        let Item::DocType(doctype) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(doctype.name, "note");
+        assert_eq!(
+            doctype.external_id(),
+            Some(&ExternalId::System {
+                system: String::from(r#"say "hi".dtd"#)
+            })
+        );
+        assert_eq!(doctype.public_id(), None);
+        assert_eq!(
+            doctype.internal_subset.as_deref(),
+            Some("\n  <!ENTITY writer \"Donald\">\n  <!-- ] -->\n")
+        );
         assert_eq!(items[0].to_string(), RAW);
+
+        assert_eq!("html".parse(), Ok(DocType::new(String::from("html"))));
         assert_eq!(
-            inner,
-            r#"html
-     PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN"
-     "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd""#
+            "html[]"
+                .parse::<DocType>()
+                .unwrap()
+                .internal_subset
+                .as_deref(),
+            Some("")
+        );
+
+        let error = |doctype: &str| doctype.parse::<DocType>().err().unwrap();
+        assert_eq!(error(""), DocTypeError::MissingName);
+        assert_eq!(error("html SYSTEM"), DocTypeError::MalformedExternalId);
+        assert_eq!(
+            error(r#"html PUBLIC "a""#),
+            DocTypeError::MalformedExternalId
         );
+        assert_eq!(error(r#"html SYSTEM "a"b"#), DocTypeError::TrailingContent);
+        assert_eq!(
+            error("html [ <!ELEMENT a ANY>"),
+            DocTypeError::UnclosedInternalSubset
+        );
+        assert_eq!(error("html foo"), DocTypeError::TrailingContent);
+
+        let mut doctype = DocType::new(String::from("a"));
+        let public = |public: &str| {
+            Some(ExternalId::Public {
+                public: String::from(public),
+                system: String::from("a.dtd"),
+            })
+        };
+        assert_eq!(
+            doctype.set_external_id(public(r#"it's "quoted""#)),
+            Err(DocTypeError::InvalidLiteral)
+        );
+        assert_eq!(doctype.external_id(), None);
+        assert_eq!(doctype.set_external_id(public("it's")), Ok(()));
+        assert_eq!(doctype.to_string(), r#"a PUBLIC "it's" "a.dtd""#);
+
+        assert!(matches!(
+            parse("<!DOCTYPE html SYSTEM>").err().unwrap(),
+            Error::MalformedDocType {
+                reason: DocTypeError::MalformedExternalId,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -80,6 +179,12 @@ mod tests {
             inner,
             r#"xml version="1.0" encoding="UTF-8" standalone="no""#
         );
+
+        // Declarations cannot end early.
+        let injected = Item::Decl(String::from("xml?><evil/><?x"));
+        assert_eq!(injected.to_string(), "<?xml? ><evil/><?x?>");
+        let items = parse(&injected.to_string()).unwrap();
+        assert_eq!(items.len(), 1);
     }
 
     #[test]
@@ -94,7 +199,58 @@ mod tests {
         };
         let text = items[0].to_string();
         assert_eq!(text, RAW);
-        assert_eq!(inner, r#"notxml something="else" "#);
+        assert_eq!(inner.target(), "notxml");
+        assert_eq!(inner.data(), r#"something="else" "#);
+        assert_eq!(inner.pseudo_attribute("something"), Some("else"));
+    }
+
+    #[test]
+    fn test_pi_structure() {
+        let items =
+            parse("<?xml-stylesheet href='a.css'\n type=\"text/css\"?><?empty?><?odd data?>")
+                .unwrap();
+        let pis: Vec<&ProcessingInstruction> = items
+            .iter()
+            .map(|item| match item {
+                Item::PI(pi) => pi,
+                _ => panic!("Item is of wrong type."),
+            })
+            .collect();
+        assert_eq!(
+            pis[0].pseudo_attributes(),
+            Some(vec![("href", "a.css"), ("type", "text/css")])
+        );
+        assert_eq!(pis[1].target(), "empty");
+        assert_eq!(pis[1].data(), "");
+        assert_eq!(items[1].to_string(), "<?empty?>");
+        assert_eq!(pis[2].pseudo_attributes(), None);
+        assert_eq!(pis[2].pseudo_attribute("data"), None);
+
+        let mut pi = ProcessingInstruction::new(String::from("app"), String::new()).unwrap();
+        assert_eq!(
+            pi.set_data(String::from("a ?> b")),
+            Err(PIError::InvalidData)
+        );
+        pi.set_data(String::from("a=\"1\"")).unwrap();
+        assert_eq!(Item::PI(pi).to_string(), "<?app a=\"1\"?>");
+
+        assert_eq!(
+            ProcessingInstruction::new(String::from("Xml"), String::new()),
+            Err(PIError::ReservedTarget(String::from("Xml")))
+        );
+        assert_eq!(
+            ProcessingInstruction::new(String::from("a b"), String::new()),
+            Err(PIError::InvalidTarget(String::from("a b")))
+        );
+
+        let error = parse("<a><?XML data?></a>").err().unwrap();
+        assert!(matches!(
+            error,
+            Error::ProcessingInstruction {
+                reason: PIError::ReservedTarget(_),
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -107,7 +263,7 @@ mod tests {
             Item::Element(e) => e,
             _ => panic!("Item is of wrong type."),
         };
-        assert_eq!(element.self_closing, false);
+        assert!(!element.self_closing);
         assert_eq!(element.name, "a");
         assert_eq!(element.attributes.len(), 0);
         assert_eq!(element.children.len(), 0);
@@ -125,7 +281,7 @@ mod tests {
             Item::Element(e) => e,
             _ => panic!("Item is of wrong type."),
         };
-        assert_eq!(element.self_closing, true);
+        assert!(element.self_closing);
         assert_eq!(element.name, "a");
         assert_eq!(element.attributes.len(), 0);
         assert_eq!(items[0].to_string(), RAW);
@@ -135,7 +291,6 @@ mod tests {
     #[test]
     fn test_element_with_attrs() {
         const RAW: &str = r#"<xyz tree="oak" material="wood"></xyz>"#;
-        const RAW_ALT: &str = r#"<xyz material="wood" tree="oak"></xyz>"#;
 
         let items = parse(RAW).unwrap();
         assert_eq!(items.len(), 1);
@@ -143,24 +298,23 @@ mod tests {
             Item::Element(e) => e,
             _ => panic!("Item is of wrong type."),
         };
-        assert_eq!(element.self_closing, false);
+        assert!(!element.self_closing);
         assert_eq!(element.name, "xyz");
         assert_eq!(element.children.len(), 0);
         assert_eq!(element.attributes.len(), 2);
-        assert!(element.attributes.get("tree").is_some());
-        assert!(element.attributes.get("material").is_some());
+        assert!(element.attributes.contains_key("tree"));
+        assert!(element.attributes.contains_key("material"));
         assert_eq!(element.attributes.get("tree").unwrap(), "oak");
         assert_eq!(element.attributes.get("material").unwrap(), "wood");
         let item_str = items[0].to_string();
-        assert!(RAW == item_str || RAW_ALT == item_str);
+        assert_eq!(item_str, RAW);
         let element_str = element.to_string();
-        assert!(RAW == element_str || RAW_ALT == element_str);
+        assert_eq!(element_str, RAW);
     }
 
     #[test]
     fn test_empty_element_with_attrs() {
         const RAW: &str = r#"<xyz tree="oak" material="wood" />"#;
-        const RAW_ALT: &str = r#"<xyz material="wood" tree="oak" />"#;
 
         let items = parse(RAW).unwrap();
         assert_eq!(items.len(), 1);
@@ -168,18 +322,18 @@ mod tests {
             Item::Element(e) => e,
             _ => panic!("Item is of wrong type."),
         };
-        assert_eq!(element.self_closing, true);
+        assert!(element.self_closing);
         assert_eq!(element.name, "xyz");
         assert_eq!(element.attributes.len(), 2);
-        assert!(element.attributes.get("tree").is_some());
-        assert!(element.attributes.get("material").is_some());
+        assert!(element.attributes.contains_key("tree"));
+        assert!(element.attributes.contains_key("material"));
         assert_eq!(element.attributes.get("tree").unwrap(), "oak");
         assert_eq!(element.attributes.get("material").unwrap(), "wood");
         let item_str = items[0].to_string();
         println!("{}", item_str);
-        assert!(RAW == item_str || RAW_ALT == item_str);
+        assert_eq!(item_str, RAW);
         let element_str = element.to_string();
-        assert!(RAW == element_str || RAW_ALT == element_str);
+        assert_eq!(element_str, RAW);
     }
 
     #[test]
@@ -206,7 +360,1720 @@ mod tests {
             Item::Element(e) => e,
             _ => panic!("Item is of wrong type."),
         };
-        assert_eq!(inner.get_decendants_at_depth(1).len(), 2);
-        assert_eq!(inner.get_decendants_at_depth(2).len(), 1);
+        assert_eq!(inner.get_decendants_at_depth(1).unwrap().len(), 2);
+        assert_eq!(inner.get_decendants_at_depth(2).unwrap().len(), 1);
+        assert!(inner.get_decendants_at_depth(0).is_none());
+    }
+
+    #[test]
+    fn test_descendant_iterators() {
+        const RAW: &str = "<a><b><c>1</c>2</b><d>3<e/></d>4</a>";
+
+        fn names<'a>(items: impl Iterator<Item = &'a Item>) -> Vec<String> {
+            items
+                .map(|item| match item {
+                    Item::Element(element) => element.name.clone(),
+                    item => item.to_string(),
+                })
+                .collect()
+        }
+
+        let items = parse(RAW).unwrap();
+        let Item::Element(a) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(
+            names(a.descendants()),
+            ["b", "c", "1", "2", "d", "3", "e", "4"]
+        );
+        assert_eq!(
+            names(a.descendants_breadth_first()),
+            ["b", "d", "4", "c", "2", "3", "e", "1"]
+        );
+        assert_eq!(names(a.descendants_at_depth(1)), ["b", "d", "4"]);
+        assert_eq!(names(a.descendants_at_depth(2)), ["c", "2", "3", "e"]);
+        assert_eq!(names(a.descendants_at_depth(3)), ["1"]);
+        assert_eq!(a.descendants_at_depth(4).count(), 0);
+        assert_eq!(a.descendants_at_depth(0).count(), 0);
+
+        let children: Vec<&str> = a.child_elements().map(|e| e.name.as_str()).collect();
+        assert_eq!(children, ["b", "d"]);
+
+        // Iteration stops early and can be resumed.
+        let mut descendants = a.descendants().skip(2);
+        assert_eq!(
+            descendants.next().map(Item::to_string).as_deref(),
+            Some("1")
+        );
+        assert_eq!(descendants.count(), 5);
+    }
+
+    #[test]
+    fn test_mutable_traversal() {
+        const RAW: &str = r#"<a xmlns:x="urn:x"><price>1</price><b><price>2<price>3</price></price><x:c/></b>text</a>"#;
+
+        let mut items = parse_with(RAW, &ParseOptions::new().namespaces(true)).unwrap();
+        let Item::Element(a) = &mut items[0] else {
+            panic!("Item is of wrong type.");
+        };
+
+        // The inner price is part of the outer one, so it is not returned separately.
+        let prices = a.find_descendants_mut(&|item| match item {
+            Item::Element(element) => element.name == "price",
+            _ => false,
+        });
+        assert_eq!(prices.len(), 2);
+        for price in prices {
+            let Item::Element(price) = price else {
+                panic!("Item is of wrong type.");
+            };
+            price
+                .attributes
+                .insert(String::from("sale"), String::from("yes"));
+        }
+
+        for item in a.descendants_mut() {
+            if let Item::Text(text) = item {
+                text.push('!');
+            }
+        }
+        for element in a.find_elements_ns_mut("urn:x", "c") {
+            element.self_closing = false;
+        }
+        for item in a.descendants_at_depth_mut(2) {
+            if let Item::Element(element) = item {
+                element.name.make_ascii_uppercase();
+            }
+        }
+        for element in a.child_elements_mut() {
+            element
+                .attributes
+                .insert(String::from("child"), String::from("1"));
+        }
+
+        assert_eq!(
+            stringify(&items),
+            r#"<a xmlns:x="urn:x"><price sale="yes" child="1">1!</price><b child="1"><PRICE sale="yes">2!<price>3!</price></PRICE><X:C></X:C></b>text!</a>"#
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let raw = "<a>".repeat(DEPTH) + &"</a>".repeat(DEPTH);
+
+        let items = parse(&raw).unwrap();
+        assert_eq!(items.len(), 1);
+        let mut depth = 0;
+        let mut current = &items[0];
+        while let Item::Element(element) = current {
+            depth += 1;
+            match element.children.first() {
+                Some(child) => current = child,
+                None => break,
+            }
+        }
+        assert_eq!(depth, DEPTH);
+        assert_eq!(items[0].to_string(), raw);
+
+        let Item::Element(root) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(root.find_descendants(&|_| true).len(), DEPTH - 1);
+        assert_eq!(root.get_text_content(), "");
+        assert_eq!(&root.clone(), root);
+        let children = root.clone().into_children();
+        assert_eq!(children.len(), 1);
+
+        let debug = format!("{items:?}");
+        assert!(debug
+            .starts_with(r#"[Element(Element { name: "a", attributes: {}, children: [Element("#));
+        assert!(debug.ends_with("self_closing: false })], self_closing: false })]"));
+        assert_eq!(root.descendants().count(), DEPTH - 1);
+        assert_eq!(root.descendants_breadth_first().count(), DEPTH - 1);
+        assert_eq!(root.descendants_at_depth(DEPTH - 1).count(), 1);
+    }
+
+    #[test]
+    fn test_wide_document() {
+        const WIDTH: usize = 200_000;
+        let raw =
+            String::from("<root>") + &"<node attr=\"value\">text</node>".repeat(WIDTH) + "</root>";
+
+        let items = parse(&raw).unwrap();
+        let Item::Element(root) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(root.children.len(), WIDTH);
+
+        // Paths are still worked out for problems within start tags.
+        let raw = raw.replace("</root>", "<node x=\"1\" x=\"2\"/></root>");
+        let err = parse(&raw).unwrap_err();
+        let expected = format!("/root/node[{}]", WIDTH + 1);
+        assert_eq!(err.position().unwrap().path, expected);
+        let options = ParseOptions::new().lenient_attributes(true);
+        let (_, warnings) = parse_with_warnings(&raw, &options).unwrap();
+        assert_eq!(warnings[0].position().unwrap().path, expected);
+    }
+
+    #[test]
+    fn test_mismatched_end_tag() {
+        assert!(parse("<a><b></a></b>").is_err());
+    }
+
+    #[test]
+    fn test_unmatched_end_tag() {
+        assert!(matches!(
+            parse("<a></a></b>"),
+            Err(Error::Xml {
+                cause: quick_xml::Error::IllFormed(IllFormedError::UnmatchedEndTag(name)),
+                ..
+            }) if name == "b"
+        ));
+    }
+
+    #[test]
+    fn test_missing_end_tag() {
+        assert!(matches!(
+            parse("<a><b></b>"),
+            Err(Error::Xml {
+                cause: quick_xml::Error::IllFormed(IllFormedError::MissingEndTag(name)),
+                ..
+            }) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        const RAW: &str = r#"<a x="y"><b>text</b><!-- comment --></a>"#;
+
+        let items = parse_reader(RAW.as_bytes()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(stringify(&items), stringify(&parse(RAW).unwrap()));
+    }
+
+    #[test]
+    fn test_parse_file() {
+        const RAW: &str = "<a><b>text</b></a>";
+
+        let path = std::env::temp_dir().join("larix_test_parse_file.xml");
+        std::fs::write(&path, RAW).unwrap();
+        let items = parse_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(stringify(&items), RAW);
+        assert!(matches!(parse_file(&path), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_error_position() {
+        const RAW: &str = "<config>\n  <servers>\n    <server />\n    <server>\n      <port></host>\n    </server>\n  </servers>\n</config>";
+
+        let position = parse(RAW).err().unwrap().position().unwrap().clone();
+        assert_eq!(position.line, 5);
+        assert_eq!(position.column, 13);
+        assert_eq!(position.offset, 61);
+        assert_eq!(position.path, "/config/servers/server[2]/port");
+
+        let reader_position = parse_reader(std::io::BufReader::with_capacity(3, RAW.as_bytes()))
+            .err()
+            .unwrap()
+            .position()
+            .unwrap()
+            .clone();
+        assert_eq!(position, reader_position);
+    }
+
+    #[test]
+    fn test_error_position_at_end() {
+        const RAW: &str = "<a>\n<b></b>\n";
+
+        let error = parse(RAW).err().unwrap();
+        let position = error.position().unwrap();
+        assert_eq!(position.line, 3);
+        assert_eq!(position.column, 1);
+        assert_eq!(position.path, "/a");
+    }
+
+    #[test]
+    fn test_spans() {
+        const RAW: &str = "<a x=\"1\" yy='22'>text<b /><!-- c --></a>";
+
+        let items = parse_with(RAW, &ParseOptions::new().spans(true)).unwrap();
+        let span_str = |span: Span| &RAW[span.start as usize..span.end as usize];
+        let element = match &items[0] {
+            Item::Element(e) => e,
+            _ => panic!("Item is of wrong type."),
+        };
+        assert_eq!(span_str(items[0].span().unwrap()), RAW);
+        assert_eq!(
+            span_str(element.start_tag_span().unwrap()),
+            "<a x=\"1\" yy='22'>"
+        );
+        assert_eq!(span_str(element.end_tag_span().unwrap()), "</a>");
+        assert_eq!(
+            span_str(element.content_span().unwrap()),
+            "text<b /><!-- c -->"
+        );
+
+        let attr = element.attribute_span("yy").unwrap();
+        assert_eq!(span_str(attr.name), "yy");
+        assert_eq!(span_str(attr.value), "22");
+        assert!(element.attribute_span("z").is_none());
+
+        assert_eq!(span_str(element.child_span(0).unwrap()), "text");
+        assert_eq!(span_str(element.child_span(1).unwrap()), "<b />");
+        assert_eq!(span_str(element.child_span(2).unwrap()), "<!-- c -->");
+        assert!(element.child_span(3).is_none());
+
+        let Item::Element(b) = &element.children[1] else {
+            panic!("Item is of wrong type.");
+        };
+        assert!(b.end_tag_span().is_none());
+        assert!(b.content_span().is_none());
+    }
+
+    #[test]
+    fn test_top_level_spans() {
+        const RAW: &str = "<?xml version=\"1.0\"?>hi<!-- c --><a>x<![CDATA[y]]></a><?pi?>";
+
+        let (items, spans) = parse_with_spans(RAW, &ParseOptions::new()).unwrap();
+        let span_str = |span: Span| &RAW[span.start as usize..span.end as usize];
+        let spanned: Vec<&str> = spans.iter().map(|span| span_str(*span)).collect();
+        assert_eq!(
+            spanned,
+            [
+                "<?xml version=\"1.0\"?>",
+                "hi",
+                "<!-- c -->",
+                "<a>x<![CDATA[y]]></a>",
+                "<?pi?>"
+            ]
+        );
+        assert_eq!(items[3].span(), Some(spans[3]));
+        let Item::Element(a) = &items[3] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(span_str(a.child_span(1).unwrap()), "<![CDATA[y]]>");
+
+        let options = ParseOptions::new().merge_cdata(true);
+        let (items, spans) = parse_with_spans("x<![CDATA[y]]>z<a/>", &options).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            spans,
+            [Span { start: 0, end: 15 }, Span { start: 15, end: 19 }]
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        const RAW: &str = "\u{feff}<a x=\"1\">t</a>";
+
+        let (items, spans) = parse_with_spans(RAW, &ParseOptions::new()).unwrap();
+        let span_str = |span: Span| &RAW[span.start as usize..span.end as usize];
+        assert_eq!(span_str(spans[0]), "<a x=\"1\">t</a>");
+        let Item::Element(a) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(span_str(a.start_tag_span().unwrap()), "<a x=\"1\">");
+        assert_eq!(span_str(a.attribute_span("x").unwrap().value), "1");
+        assert_eq!(span_str(a.child_span(0).unwrap()), "t");
+
+        let located = |raw: &str| {
+            let from_str = parse(raw).unwrap_err();
+            let from_reader = parse_reader(raw.as_bytes()).unwrap_err();
+            let position = from_str.position().unwrap();
+            assert_eq!(from_reader.position(), Some(position));
+            (position.offset, position.line, position.column)
+        };
+        assert_eq!(located("\u{feff}<a>\n<b x='1' x='2'/></a>"), (16, 2, 10));
+        assert_eq!(located("\u{feff}<a>\n<b></c></a>"), (10, 2, 4));
+    }
+
+    #[test]
+    fn test_no_spans_by_default() {
+        let items = parse("<a x=\"1\"></a>").unwrap();
+        assert!(items[0].span().is_none());
+    }
+
+    #[test]
+    fn test_options_drop() {
+        const RAW: &str = "<a><!-- comment --><?pi data?>text</a>";
+
+        let options = ParseOptions::new()
+            .drop_comments(true)
+            .drop_processing_instructions(true);
+        let items = parse_with(RAW, &options).unwrap();
+        assert_eq!(stringify(&items), "<a>text</a>");
+    }
+
+    #[test]
+    fn test_options_merge_cdata() {
+        const RAW: &str = "<a>x<![CDATA[<y>]]>z<b /></a>";
+
+        let options = ParseOptions::new().merge_cdata(true);
+        let items = parse_with(RAW, &options).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(element.children.len(), 2);
+        assert!(matches!(&element.children[0], Item::Text(text) if text == "x<y>z"));
+        assert_eq!(items[0].to_string(), "<a>x&lt;y&gt;z<b /></a>");
+
+        let items = parse_with(RAW, &options.spans(true)).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        let span = element.child_span(0).unwrap();
+        assert_eq!(
+            &RAW[span.start as usize..span.end as usize],
+            "x<![CDATA[<y>]]>z"
+        );
+    }
+
+    #[test]
+    fn test_options_reject_doctype() {
+        const RAW: &str = "<!DOCTYPE a><a />";
+
+        assert!(parse(RAW).is_ok());
+        let options = ParseOptions::new().reject_doctype(true);
+        assert!(matches!(
+            parse_with(RAW, &options),
+            Err(Error::DocType { position }) if position.offset == 0
+        ));
+    }
+
+    #[test]
+    fn test_options_attributes() {
+        const RAW: &str = r#"<a x="1" x="2" y="3" />"#;
+
+        let lenient = ParseOptions::new().lenient_attributes(true);
+        let items = parse_with(RAW, &lenient).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(element.attributes.len(), 2);
+
+        let strict = ParseOptions::new().lenient_attributes(false);
+        assert!(matches!(
+            parse_with(RAW, &strict),
+            Err(Error::Attribute { .. })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_attributes() {
+        let cases = [
+            (r#"<a x="1" x="2" />"#, "x", 9),
+            (r#"<a><b key other="1" /></a>"#, "key", 10),
+            ("<a key=value />", "key", 7),
+        ];
+
+        for (raw, name, offset) in cases {
+            let Err(Error::Attribute {
+                attribute,
+                position,
+                ..
+            }) = parse(raw)
+            else {
+                panic!("Malformed attribute was not reported for {raw}.");
+            };
+            assert_eq!(attribute, name, "{raw}");
+            assert_eq!(position.offset, offset, "{raw}");
+        }
+
+        let Err(Error::Attribute { element, .. }) = parse(r#"<a><b x="1" x="2" /></a>"#) else {
+            panic!("Malformed attribute was not reported.");
+        };
+        assert_eq!(element, "b");
+    }
+
+    #[test]
+    fn test_attribute_warnings() {
+        const RAW: &str = "<a x=\"1\" x=\"2\">\n<b y=2 z=\"3\" /></a>";
+
+        let options = ParseOptions::new().lenient_attributes(true);
+        let (items, warnings) = parse_with_warnings(RAW, &options).unwrap();
+        assert_eq!(stringify(&items), "<a x=\"1\">\n<b z=\"3\" /></a>");
+        assert_eq!(warnings.len(), 2);
+        let position = warnings[1].position().unwrap();
+        assert_eq!((position.line, position.column), (2, 6));
+        assert_eq!(position.path, "/a/b");
+        assert_eq!(warnings[0].position().unwrap().path, "/a");
+    }
+
+    #[test]
+    fn test_start_tag_error_path() {
+        const RAW: &str =
+            "<config><servers><server/><server/><server port=\"1\" port=\"2\"/></servers></config>";
+
+        let err = parse(RAW).unwrap_err();
+        assert!(matches!(err, Error::Attribute { .. }));
+        assert_eq!(err.position().unwrap().path, "/config/servers/server[3]");
+
+        let options = ParseOptions::new().max_attributes(1);
+        let err = parse_with("<a><b x=\"1\" y=\"2\"></b></a>", &options).unwrap_err();
+        assert_eq!(err.position().unwrap().path, "/a/b");
+
+        let options = ParseOptions::new().namespaces(true);
+        let err = parse_with("<a><p:b /></a>", &options).unwrap_err();
+        assert_eq!(err.position().unwrap().path, "/a/p:b");
+
+        // Errors after the start tag are located at the parent again.
+        let err = parse("<a><b /></c></a>").unwrap_err();
+        assert_eq!(err.position().unwrap().path, "/a");
+    }
+
+    #[test]
+    fn test_entities() {
+        const RAW: &str =
+            r#"<a title="&quot;Tom &amp; Jerry&quot;">&lt;&#x20AC;&#8364;&gt; &amp; more</a>"#;
+
+        let items = parse(RAW).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(
+            element.attributes.get("title").map(String::as_str),
+            Some(r#""Tom & Jerry""#)
+        );
+        assert_eq!(element.get_text_content(), "<€€> & more");
+        assert_eq!(
+            items[0].to_string(),
+            r#"<a title="&quot;Tom &amp; Jerry&quot;">&lt;€€&gt; &amp; more</a>"#
+        );
+    }
+
+    #[test]
+    fn test_entities_raw() {
+        const RAW: &str = r#"<a title="&amp;">&lt;&#x20AC;</a>"#;
+
+        let items = parse_with(RAW, &ParseOptions::new().raw_values(true)).unwrap();
+        let Item::Element(element) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(
+            element.attributes.get("title").map(String::as_str),
+            Some("&amp;")
+        );
+        assert_eq!(element.get_text_content(), "&lt;&#x20AC;");
+
+        let raw = SerializeOptions::new().raw_values(true);
+        assert_eq!(stringify_with(&items, &raw), RAW);
+        assert_eq!(
+            items[0].to_string(),
+            r#"<a title="&amp;amp;">&amp;lt;&amp;#x20AC;</a>"#
+        );
+
+        const QUOTED: &str = r#"<a x='say "hi"' y="it's">&quot;</a>"#;
+        let items = parse_with(QUOTED, &ParseOptions::new().raw_values(true)).unwrap();
+        assert_eq!(stringify_with(&items, &raw), QUOTED);
+        let decoded = parse(&stringify_with(&items, &raw)).unwrap();
+        assert_eq!(decoded, parse(QUOTED).unwrap());
+    }
+
+    #[test]
+    fn test_unknown_entity() {
+        assert!(matches!(
+            parse("<a>&unknown;</a>"),
+            Err(Error::Xml {
+                cause: quick_xml::Error::EscapeError(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_escape_attribute() {
+        let mut element = Element::new(String::from("a"));
+        element
+            .attributes
+            .insert(String::from("x"), String::from("a\"b<c>&'d\n\te"));
+
+        let raw = element.to_string();
+        assert_eq!(raw, r#"<a x="a&quot;b&lt;c&gt;&amp;'d&#10;&#9;e"></a>"#);
+
+        let items = parse(&raw).unwrap();
+        let Item::Element(parsed) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(parsed.attributes.get("x"), element.attributes.get("x"));
+    }
+
+    #[test]
+    fn test_escape_text() {
+        let mut element = Element::new(String::from("a"));
+        element
+            .children
+            .push(Item::Text(String::from("</a><b>&amp;\r\n")));
+        element.children.push(Item::CData(String::from("x]]>y")));
+
+        let raw = element.to_string();
+        assert_eq!(
+            raw,
+            "<a>&lt;/a&gt;&lt;b&gt;&amp;amp;&#13;\n<![CDATA[x]]]]><![CDATA[>y]]></a>"
+        );
+
+        let items = parse(&raw).unwrap();
+        let Item::Element(parsed) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(parsed.children.len(), 3);
+        assert_eq!(parsed.get_text_content(), "</a><b>&amp;\r\n");
+    }
+
+    #[test]
+    fn test_attribute_order() {
+        const RAW: &str = r#"<a z="1" b="2" y="3" a="4" />"#;
+
+        let mut items = parse(RAW).unwrap();
+        assert_eq!(stringify(&items), RAW);
+
+        let Item::Element(element) = &mut items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        element
+            .attributes
+            .insert(String::from("b"), String::from("5"));
+        element
+            .attributes
+            .insert(String::from("c"), String::from("6"));
+        assert_eq!(element.attributes.remove("y").unwrap(), "3");
+        assert_eq!(element.to_string(), r#"<a z="1" b="5" a="4" c="6" />"#);
+    }
+
+    #[test]
+    fn test_namespaces() {
+        const RAW: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:x="http://www.w3.org/1999/xlink"><x:a x:href="#r" id="1"><rect xmlns="" xml:lang="en" /></x:a></svg>"##;
+
+        let items = parse_with(RAW, &ParseOptions::new().namespaces(true)).unwrap();
+        let Item::Element(svg) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(svg.namespace(), Some("http://www.w3.org/2000/svg"));
+        assert_eq!(svg.prefix(), None);
+        let xmlns = &svg.attributes.as_slice()[1];
+        assert_eq!(xmlns.prefix(), Some("xmlns"));
+        assert_eq!(xmlns.local_name(), "x");
+        assert_eq!(xmlns.namespace(), Some(XMLNS_NAMESPACE));
+
+        let Item::Element(a) = &svg.children[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(a.prefix(), Some("x"));
+        assert_eq!(a.local_name(), "a");
+        assert_eq!(a.namespace(), Some("http://www.w3.org/1999/xlink"));
+        assert_eq!(
+            a.attribute_ns("http://www.w3.org/1999/xlink", "href")
+                .unwrap(),
+            "#r"
+        );
+        assert!(a.attribute_ns("http://www.w3.org/2000/svg", "id").is_none());
+        assert_eq!(a.attributes.as_slice()[1].namespace(), None);
+
+        let Item::Element(rect) = &a.children[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(rect.namespace(), None);
+        assert_eq!(rect.attribute_ns(XML_NAMESPACE, "lang").unwrap(), "en");
+
+        assert_eq!(
+            svg.find_elements_ns("http://www.w3.org/1999/xlink", "a")
+                .len(),
+            1
+        );
+        assert_eq!(stringify(&items), RAW);
+    }
+
+    #[test]
+    fn test_namespaces_unknown_prefix() {
+        const RAW: &str = "<a><b:c /></a>";
+
+        assert!(parse(RAW).is_ok());
+        assert!(matches!(
+            parse_with(RAW, &ParseOptions::new().namespaces(true)),
+            Err(Error::Xml {
+                cause: quick_xml::Error::UnknownPrefix(_),
+                position,
+            }) if position.offset == 3
+        ));
+        assert!(parse_with(
+            r#"<a xmlns:b="urn:b"><b:c /></a><b:c />"#,
+            &ParseOptions::new().namespaces(true)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_namespaces_invalid_bindings() {
+        let options = ParseOptions::new().namespaces(true);
+
+        assert!(matches!(
+            parse_with(r#"<a xmlns:p=""><p:b /></a>"#, &options),
+            Err(Error::Xml {
+                cause: quick_xml::Error::InvalidPrefixBind { .. },
+                ..
+            })
+        ));
+        assert!(parse_with(r#"<a xmlns=""><b /></a>"#, &options).is_ok());
+
+        for raw in [
+            r#"<a xmlns:="urn:x"><b /></a>"#,
+            "<:b />",
+            "<p: xmlns:p=\"urn:x\" />",
+            "<p:b:c xmlns:p=\"urn:x\" />",
+            r#"<a xmlns:p="urn:x" p:="1" />"#,
+            r#"<a :x="1" />"#,
+        ] {
+            assert!(parse(raw).is_ok());
+            assert!(
+                matches!(
+                    parse_with(raw, &options),
+                    Err(Error::Xml {
+                        cause: quick_xml::Error::UnknownPrefix(_),
+                        ..
+                    })
+                ),
+                "{raw}"
+            );
+        }
+
+        const RAW: &str = r#"<a xmlns:p="urn:x" xmlns:q="urn:x" p:x="1" q:x="2" />"#;
+        assert!(parse(RAW).is_ok());
+        let err = parse_with(RAW, &options).unwrap_err();
+        let Error::Attribute {
+            attribute,
+            cause: quick_xml::events::attributes::AttrError::Duplicated(duplicate, earlier),
+            position,
+            ..
+        } = &err
+        else {
+            panic!("Wrong error: {err:?}");
+        };
+        assert_eq!(attribute, "q:x");
+        assert_eq!(&RAW[*earlier + 1..*duplicate + 1], r#"p:x="1" "#);
+        assert_eq!(position.offset as usize, RAW.find("q:x").unwrap());
+        assert_eq!(position.path, "/a");
+
+        let (items, warnings) =
+            parse_with_warnings(RAW, &options.clone().lenient_attributes(true)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            stringify(&items),
+            r#"<a xmlns:p="urn:x" xmlns:q="urn:x" p:x="1" />"#
+        );
+    }
+
+    #[test]
+    fn test_pretty() {
+        const RAW: &str = "<?xml version=\"1.0\"?>\n<doc>\n <!-- c --><p>Hello <b>big <i>world</i></b></p>  <list><item /><item>x</item></list><empty>  </empty></doc>";
+
+        let items = parse(RAW).unwrap();
+        let options = SerializeOptions::pretty().strip_whitespace(true);
+        let pretty = stringify_with(&items, &options);
+        assert_eq!(
+            pretty,
+            "<?xml version=\"1.0\"?>
+<doc>
+  <!-- c -->
+  <p>Hello <b>big <i>world</i></b></p>
+  <list>
+    <item />
+    <item>x</item>
+  </list>
+  <empty></empty>
+</doc>"
+        );
+        assert_eq!(
+            stringify(&parse_trimmed(&pretty).unwrap()),
+            stringify(&parse_trimmed(RAW).unwrap())
+        );
+        assert_eq!(stringify_with(&items, &SerializeOptions::new()), RAW);
+
+        // Without stripping, the whitespace of the input is significant, so nothing is added.
+        assert_eq!(stringify_with(&items, &SerializeOptions::pretty()), RAW);
+    }
+
+    #[test]
+    fn test_pretty_preserves_content() {
+        const MIXED: &str = "<p><b>a</b> <i>b</i></p>";
+        const PRESERVE: &str = r#"<r><a xml:space="preserve"> <b/> </a><c><d/></c></r>"#;
+
+        let items = parse(MIXED).unwrap();
+        let pretty = stringify_with(&items, &SerializeOptions::pretty());
+        assert_eq!(pretty, "<p><b>a</b> <i>b</i></p>");
+        let Item::Element(p) = &parse(&pretty).unwrap()[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(p.get_text_content(), "a b");
+
+        let items = parse(PRESERVE).unwrap();
+        let expected = r#"<r>
+  <a xml:space="preserve"> <b /> </a>
+  <c>
+    <d />
+  </c>
+</r>"#;
+        let options = SerializeOptions::pretty().strip_whitespace(true);
+        assert_eq!(stringify_with(&items, &options), expected);
+        let items = parse(r#"<r><a xml:space="preserve"><b><c/></b></a></r>"#).unwrap();
+        assert_eq!(
+            stringify_with(&items, &options),
+            "<r>\n  <a xml:space=\"preserve\"><b><c /></b></a>\n</r>"
+        );
+    }
+
+    #[test]
+    fn test_pretty_wrap_attributes() {
+        const RAW: &str = r#"<a><b first="1" second="2" third="3"><c /></b><d x="1" /></a>"#;
+
+        let options = SerializeOptions::new()
+            .indent(Some(String::from("\t")))
+            .max_width(20)
+            .wrap_attributes(true);
+        let items = parse(RAW).unwrap();
+        assert_eq!(
+            items[0].to_string_with(&options),
+            "<a>
+\t<b
+\t\tfirst=\"1\"
+\t\tsecond=\"2\"
+\t\tthird=\"3\">
+\t\t<c />
+\t</b>
+\t<d x=\"1\" />
+</a>"
+        );
+    }
+
+    #[test]
+    fn test_write_to() {
+        const RAW: &str = r#"<a x="&quot;"><b>1 &lt; 2</b><![CDATA[]]>]]&gt;<!-- c --></a>"#;
+
+        let items = parse(RAW).unwrap();
+        let mut out = Vec::new();
+        write_items(&items, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), RAW);
+
+        let Item::Element(element) = &items[0] else {
+            panic!("Item is of wrong type.");
+        };
+        let mut out = Vec::new();
+        element
+            .write_to_with(&mut out, &SerializeOptions::pretty())
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            element.to_string_with(&SerializeOptions::pretty())
+        );
+
+        let mut full = [0u8; 10];
+        let error = items[0].write_to(&mut &mut full[..]).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_document() {
+        const RAW: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- prolog -->
+<!DOCTYPE root>
+<?style href="a.css"?>
+<root><child /></root>
+<!-- epilog -->
+"#;
+
+        let mut document = parse_document(RAW).unwrap();
+        assert_eq!(
+            document.decl(),
+            Some(r#"xml version="1.0" encoding="UTF-8""#)
+        );
+        assert_eq!(
+            document.doctype(),
+            Some(&DocType::new(String::from("root")))
+        );
+        assert_eq!(document.root().name, "root");
+        assert!(document.prolog().iter().any(
+            |item| matches!(item, Item::PI(pi) if pi.pseudo_attribute("href") == Some("a.css"))
+        ));
+        assert!(matches!(&document.epilog()[1], Item::Comment(c) if c == " epilog "));
+        assert_eq!(document.to_string(), RAW);
+
+        document.root_mut().children.clear();
+        assert_eq!(
+            document.to_string_with(&SerializeOptions::pretty().strip_whitespace(true)),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- prolog -->
+<!DOCTYPE root>
+<?style href="a.css"?>
+<root></root>
+<!-- epilog -->"#
+        );
+        assert_eq!(
+            stringify(&document.into_items()),
+            RAW.replace("<child />", "")
+        );
+
+        assert_eq!(
+            Document::new(Element::new(String::from("a"))).to_string(),
+            "<a></a>"
+        );
+    }
+
+    #[test]
+    fn test_document_structure() {
+        let reason = |xml: &str| match parse_document(xml).err().unwrap() {
+            Error::Document { reason, position } => (reason, position.line, position.column),
+            error => panic!("Unexpected error: {error}"),
+        };
+
+        assert_eq!(reason("<!-- a -->\n"), (DocumentError::MissingRoot, 2, 1));
+        assert_eq!(reason(""), (DocumentError::MissingRoot, 1, 1));
+        assert_eq!(
+            reason("<a></a>\n  <b />"),
+            (DocumentError::MultipleRoots, 2, 3)
+        );
+        assert_eq!(
+            reason("text<a></a>"),
+            (DocumentError::TextOutsideRoot, 1, 1)
+        );
+        assert_eq!(
+            reason("<a></a><![CDATA[x]]>"),
+            (DocumentError::TextOutsideRoot, 1, 8)
+        );
+
+        assert_eq!(
+            reason("<a/><!DOCTYPE a>"),
+            (DocumentError::MisplacedDocType, 1, 5)
+        );
+        assert_eq!(
+            reason("<!DOCTYPE a><!DOCTYPE b><a/>"),
+            (DocumentError::MultipleDocTypes, 1, 13)
+        );
+        assert_eq!(
+            reason("<!-- c --><?xml version=\"1.0\"?><a/>"),
+            (DocumentError::MisplacedDecl, 1, 11)
+        );
+        assert_eq!(
+            reason("<a/><?xml version=\"1.0\"?>"),
+            (DocumentError::MisplacedDecl, 1, 5)
+        );
+        assert_eq!(
+            reason("<a><?xml version=\"1.0\"?></a>"),
+            (DocumentError::MisplacedDecl, 1, 4)
+        );
+        assert_eq!(
+            reason(" <?xml version=\"1.0\"?><a/>"),
+            (DocumentError::MisplacedDecl, 1, 2)
+        );
+
+        let items = |xml: &str| parse(xml).unwrap();
+        assert_eq!(
+            Document::from_items(items("<a/><!DOCTYPE a>")),
+            Err(DocumentError::MisplacedDocType)
+        );
+        assert_eq!(
+            Document::from_items(items("<!DOCTYPE a><!DOCTYPE b><a/>")),
+            Err(DocumentError::MultipleDocTypes)
+        );
+        assert_eq!(
+            Document::from_items(items("<a/><?xml version=\"1.0\"?>")),
+            Err(DocumentError::MisplacedDecl)
+        );
+
+        assert!(parse_document(" <a>text</a> \n").is_ok());
+        assert!(parse_document("\u{feff}<?xml version=\"1.0\"?><!DOCTYPE a><a/>").is_ok());
+        assert!(parse("<a></a><b />").is_ok());
+    }
+
+    #[test]
+    fn test_xml_decl() {
+        let decl: XmlDecl = "xml version='1.1'\n encoding = \"UTF-8\" standalone=\"no\""
+            .parse()
+            .unwrap();
+        assert_eq!(decl.version(), "1.1");
+        assert_eq!(decl.encoding(), Some("UTF-8"));
+        assert_eq!(decl.standalone(), Some(false));
+        assert_eq!(
+            decl.to_string(),
+            r#"xml version="1.1" encoding="UTF-8" standalone="no""#
+        );
+        let mut standalone = XmlDecl::default();
+        standalone.set_standalone(Some(true));
+        assert_eq!(
+            "xml version=\"1.0\" standalone=\"yes\"".parse(),
+            Ok(standalone)
+        );
+
+        let error = |decl: &str| decl.parse::<XmlDecl>().err().unwrap();
+        assert_eq!(
+            error(r#"xml version="2.0""#),
+            DeclError::InvalidVersion(String::from("2.0"))
+        );
+        assert_eq!(
+            error(r#"xml version="1.""#),
+            DeclError::InvalidVersion(String::from("1."))
+        );
+        assert_eq!(
+            error(r#"xml version="1.0" standalone="maybe""#),
+            DeclError::InvalidStandalone(String::from("maybe"))
+        );
+        assert_eq!(
+            error(r#"xml version="1.0" encoding="8bit""#),
+            DeclError::InvalidEncoding(String::from("8bit"))
+        );
+        assert_eq!(error(r#"xml encoding="UTF-8""#), DeclError::MissingVersion);
+        assert_eq!(
+            error(r#"xml version="1.0" standalone="no" encoding="UTF-8""#),
+            DeclError::Malformed
+        );
+        assert_eq!(error(r#"xml version="1.0"#), DeclError::Malformed);
+        assert_eq!(error(r#"xmlversion="1.0""#), DeclError::Malformed);
+
+        let mut document = parse_document(r#"<?xml version="1.0"?><a />"#).unwrap();
+        let mut decl = document.xml_decl().unwrap().unwrap();
+        decl.set_encoding(Some(String::from("UTF-8"))).unwrap();
+        document.set_xml_decl(decl);
+        assert_eq!(
+            document.to_string(),
+            r#"<?xml version="1.0" encoding="UTF-8"?><a />"#
+        );
+
+        let mut document = parse_document("<a />").unwrap();
+        assert_eq!(document.xml_decl(), Ok(None));
+        document.set_xml_decl(XmlDecl::default());
+        assert_eq!(document.to_string(), r#"<?xml version="1.0"?><a />"#);
+
+        let mut decl = XmlDecl::default();
+        assert_eq!(
+            decl.set_encoding(Some(String::from("a\"?><evil/>"))),
+            Err(DeclError::InvalidEncoding(String::from("a\"?><evil/>")))
+        );
+        assert_eq!(
+            decl.set_version(String::from("1.0\"?>")),
+            Err(DeclError::InvalidVersion(String::from("1.0\"?>")))
+        );
+        assert!(XmlDecl::new(String::from("2.0")).is_err());
+        assert_eq!(XmlDecl::try_from(&Item::from(decl.clone())), Ok(decl));
+    }
+
+    #[test]
+    fn test_internal_subset() {
+        const RAW: &str = r#"<!DOCTYPE note [
+  <!ENTITY writer "Donald &amp; &who;">
+  <!ENTITY who "&#68;uck">
+  <!ENTITY writer "ignored">
+  <!ENTITY % param "ignored">
+  <!ENTITY logo SYSTEM "logo.gif">
+  <!ELEMENT note (to, (from | by)*)>
+  <!ATTLIST note
+    lang CDATA "en"
+    by CDATA #FIXED "&writer;"
+    type (short | long) 'short'
+    id ID #IMPLIED>
+  <!ATTLIST note lang CDATA "de">
+  <!-- <!ENTITY commented "out"> -->
+  <?pi data?>
+  %param;
+]>
+<note lang="fr"><from title="&who;">&writer;!</from></note>"#;
+
+        let document = parse_document(RAW).unwrap();
+        let note = document.root();
+        assert_eq!(note.attributes.get("lang").unwrap(), "fr");
+        assert_eq!(note.attributes.get("by").unwrap(), "Donald & Duck");
+        assert_eq!(note.attributes.get("type").unwrap(), "short");
+        assert!(!note.attributes.contains_key("id"));
+        assert_eq!(note.get_text_content(), "Donald & Duck!");
+        let Item::Element(from) = &note.children[0] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(from.attributes.get("title").unwrap(), "Duck");
+
+        let options = ParseOptions::new().raw_values(true);
+        let document = parse_document_with(RAW, &options).unwrap();
+        assert_eq!(document.root().attributes.get("by").unwrap(), "&writer;");
+        assert_eq!(document.root().get_text_content(), "&writer;!");
+
+        assert!(matches!(
+            parse(r#"<!DOCTYPE a [<!ENTITY logo SYSTEM "logo.gif">]><a>&logo;</a>"#),
+            Err(Error::Xml {
+                cause: quick_xml::Error::EscapeError(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("<!DOCTYPE a [<!ENTITY a \"&b;\"><!ENTITY b \"&a;\">]><a>&a;</a>"),
+            Err(Error::MalformedDocType {
+                reason: DocTypeError::RecursiveEntity,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("<!DOCTYPE a [<!ENTITY a>]><a />"),
+            Err(Error::MalformedDocType {
+                reason: DocTypeError::MalformedDeclaration,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("<!DOCTYPE a [<!ATTLIST a b CDATA>]><a />"),
+            Err(Error::MalformedDocType {
+                reason: DocTypeError::MalformedDeclaration,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_entity_limits() {
+        const LAUGHS: &str = r#"<!DOCTYPE lolz [
+  <!ENTITY lol "lol">
+  <!ENTITY lol1 "&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;">
+  <!ENTITY lol2 "&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;">
+  <!ENTITY lol3 "&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;">
+  <!ENTITY lol4 "&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;">
+  <!ENTITY lol5 "&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;">
+  <!ENTITY lol6 "&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;">
+  <!ENTITY lol7 "&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;">
+  <!ENTITY lol8 "&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;">
+  <!ENTITY lol9 "&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;">
+]>
+<lolz>&lol9;</lolz>"#;
+
+        let limit = |result: Result<Vec<Item>, Error>| match result.err().unwrap() {
+            Error::Limit { limit, position } => (limit, position.line),
+            error => panic!("Unexpected error: {error}"),
+        };
+
+        assert_eq!(limit(parse(LAUGHS)), (Limit::EntityExpansion, 13));
+        assert_eq!(
+            limit(parse_with(LAUGHS, &ParseOptions::new().max_entity_depth(5))),
+            (Limit::EntityDepth, 13)
+        );
+
+        let small = LAUGHS.replace("&lol9;", "&lol2;");
+        let items = parse(&small).unwrap();
+        let Item::Element(lolz) = &items[2] else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(lolz.get_text_content(), "lol".repeat(100));
+        assert_eq!(
+            limit(parse_with(
+                &small,
+                &ParseOptions::new().max_entity_expansion(299)
+            )),
+            (Limit::EntityExpansion, 13)
+        );
+        assert!(parse_with(&small, &ParseOptions::new().max_entity_expansion(1000)).is_ok());
+    }
+
+    #[test]
+    fn test_limits() {
+        const RAW: &str = r#"<a x="1" y="22">
+  <b><c>text</c></b>
+  <!-- comment -->
+</a>"#;
+
+        let limit = |options: ParseOptions| match parse_with(RAW, &options).err().unwrap() {
+            Error::Limit { limit, position } => (limit, position.line, position.column),
+            error => panic!("Unexpected error: {error}"),
+        };
+
+        assert_eq!(
+            limit(ParseOptions::new().max_depth(2)),
+            (Limit::Depth, 2, 6)
+        );
+        assert_eq!(
+            limit(ParseOptions::new().max_input_size(20)),
+            (Limit::InputSize, 2, 3)
+        );
+        assert_eq!(
+            limit(ParseOptions::new().max_attributes(1)),
+            (Limit::Attributes, 1, 1)
+        );
+        assert_eq!(
+            limit(ParseOptions::new().max_attribute_length(1)),
+            (Limit::AttributeLength, 1, 1)
+        );
+        assert_eq!(
+            limit(ParseOptions::new().max_text_length(3)),
+            (Limit::TextLength, 2, 9)
+        );
+        assert_eq!(
+            limit(ParseOptions::new().max_items(7)),
+            (Limit::Items, 3, 19)
+        );
+
+        let options = ParseOptions::new()
+            .max_depth(3)
+            .max_input_size(RAW.len() as u64)
+            .max_attributes(2)
+            .max_attribute_length(2)
+            .max_text_length(4)
+            .max_items(8);
+        assert!(parse_with(RAW, &options).is_ok());
+        assert!(parse_reader_with(RAW.as_bytes(), &options).is_ok());
+
+        let options = ParseOptions::new().max_input_size(RAW.len() as u64 - 1);
+        assert!(matches!(
+            parse_reader_with(RAW.as_bytes(), &options),
+            Err(Error::Limit {
+                limit: Limit::InputSize,
+                ..
+            })
+        ));
+
+        let merged = "<a>abc<![CDATA[def]]></a>";
+        let options = ParseOptions::new().merge_cdata(true).max_text_length(5);
+        assert!(matches!(
+            parse_with(merged, &options),
+            Err(Error::Limit {
+                limit: Limit::TextLength,
+                ..
+            })
+        ));
+        assert!(parse_with(merged, &options.max_text_length(6)).is_ok());
+    }
+
+    #[test]
+    fn test_traits() {
+        fn hash(item: &impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            item.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let items = parse(r#"<a x="1" y="2">t<b/><!--c--></a>"#).unwrap();
+        let reordered = parse(r#"<a y="2" x="1">t<b/><!--c--></a>"#).unwrap();
+        let with_spans = parse_with(
+            r#"<a  y="2"  x="1">t<b/><!--c--></a>"#,
+            &ParseOptions::new().spans(true),
+        )
+        .unwrap();
+        assert_eq!(items, reordered);
+        assert_eq!(items, with_spans);
+        assert_eq!(hash(&items), hash(&reordered));
+        assert_eq!(hash(&items), hash(&with_spans));
+
+        let mut copy = items.clone();
+        assert_eq!(copy, items);
+        let Item::Element(element) = &mut copy[0] else {
+            panic!("Item is of wrong type.");
+        };
+        element.children.pop();
+        assert_ne!(copy, items);
+        assert_ne!(hash(&copy), hash(&items));
+
+        let set: HashSet<Item> = items.into_iter().chain(reordered).chain(copy).collect();
+        assert_eq!(set.len(), 2);
+
+        let items = parse(r#"<a x="1">t<b/></a>"#).unwrap();
+        assert_eq!(
+            format!("{:?}", items[0]),
+            r#"Element(Element { name: "a", attributes: {"x": "1"}, children: [Text("t"), Element(Element { name: "b", attributes: {}, children: [], self_closing: true })], self_closing: false })"#
+        );
+
+        assert_eq!(
+            format!("{:#?}", items[0]),
+            r#"Element(
+    Element {
+        name: "a",
+        attributes: {
+            "x": "1",
+        },
+        children: [
+            Text(
+                "t",
+            ),
+            Element(
+                Element {
+                    name: "b",
+                    attributes: {},
+                    children: [],
+                    self_closing: true,
+                },
+            ),
+        ],
+        self_closing: false,
+    },
+)"#
+        );
+
+        let document = parse_document("<?xml version=\"1.0\"?><a/>").unwrap();
+        assert_eq!(document.clone(), document);
+    }
+
+    #[test]
+    fn test_visitor() {
+        /** Records the callbacks, stopping at the comment. */
+        #[derive(Default)]
+        struct Log(Vec<String>);
+
+        impl Visitor for Log {
+            fn enter_element(&mut self, element: &Element) -> Visit {
+                self.0.push(format!("<{}>", element.name));
+                if element.name == "skip" {
+                    Visit::Skip
+                } else {
+                    Visit::Continue
+                }
+            }
+
+            fn leave_element(&mut self, element: &Element) -> Visit {
+                self.0.push(format!("</{}>", element.name));
+                Visit::Continue
+            }
+
+            fn visit_text(&mut self, text: &str) -> Visit {
+                self.0.push(text.to_owned());
+                Visit::Continue
+            }
+
+            fn visit_comment(&mut self, _comment: &str) -> Visit {
+                Visit::Stop
+            }
+        }
+
+        let items = parse("<a>1<skip>2</skip><b>3</b><!--c-->4</a>5").unwrap();
+        let mut log = Log::default();
+        walk(&items, &mut log);
+        assert_eq!(log.0, ["<a>", "1", "<skip>", "</skip>", "<b>", "3", "</b>"]);
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        /** Wraps text in <em>, removes <del>, uppercases names on leaving and stops at <stop>. */
+        struct Edits;
+
+        impl VisitorMut for Edits {
+            fn enter_element(&mut self, element: &mut Element) -> Edit {
+                match element.name.as_str() {
+                    "del" => Edit::Remove,
+                    "stop" => Edit::Stop,
+                    _ => Edit::Continue,
+                }
+            }
+
+            fn leave_element(&mut self, element: &mut Element) -> Edit {
+                element.name.make_ascii_uppercase();
+                Edit::Continue
+            }
+
+            fn visit_text(&mut self, text: &mut String) -> Edit {
+                let mut em = Element::new(String::from("em"));
+                em.children.push(Item::Text(std::mem::take(text)));
+                Edit::Replace(Item::Element(em))
+            }
+        }
+
+        let mut items = parse("<a>1<del>2</del><b>3</b><stop>4</stop>5<c>6</c></a>").unwrap();
+        walk_mut(&mut items, &mut Edits);
+        // Everything after <stop> is kept as it was, and the open elements are not left.
+        assert_eq!(
+            stringify(&items),
+            "<a><em>1</em><B><em>3</em></B><stop>4</stop>5<c>6</c></a>"
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        /** Unwraps <span>, drops comments and counts the folded elements. */
+        #[derive(Default)]
+        struct Unwrap(usize);
+
+        impl Fold for Unwrap {
+            fn enter_element(&mut self, element: &Element) -> Visit {
+                if element.name == "pre" {
+                    Visit::Skip
+                } else {
+                    Visit::Continue
+                }
+            }
+
+            fn fold_element(&mut self, mut element: Element) -> Option<Item> {
+                self.0 += 1;
+                if element.name == "span" {
+                    Some(Item::Text(element.get_text_content()))
+                } else {
+                    element.attributes.clear();
+                    Some(Item::Element(element))
+                }
+            }
+
+            fn fold_comment(&mut self, _comment: String) -> Option<Item> {
+                None
+            }
+        }
+
+        let items = parse(
+            r#"<p class="x"><span>a<!--c--></span><pre class="y"><span>b</span><!--d--></pre></p>"#,
+        )
+        .unwrap();
+        let mut folder = Unwrap::default();
+        let folded = fold(items, &mut folder);
+        assert_eq!(
+            stringify(&folded),
+            "<p>a<pre><span>b</span><!--d--></pre></p>"
+        );
+        assert_eq!(folder.0, 3);
+    }
+
+    #[test]
+    fn test_deep_walk() {
+        const DEPTH: usize = 100_000;
+
+        struct Count(usize);
+
+        impl Visitor for Count {
+            fn leave_element(&mut self, _element: &Element) -> Visit {
+                self.0 += 1;
+                Visit::Continue
+            }
+        }
+
+        impl VisitorMut for Count {
+            fn enter_element(&mut self, element: &mut Element) -> Edit {
+                element.name.push('b');
+                Edit::Continue
+            }
+        }
+
+        impl Fold for Count {}
+
+        let raw = "<a>".repeat(DEPTH) + &"</a>".repeat(DEPTH);
+        let mut items = parse(&raw).unwrap();
+
+        let mut count = Count(0);
+        walk(&items, &mut count);
+        walk_mut(&mut items, &mut count);
+        let items = fold(items, &mut count);
+        assert_eq!(count.0, DEPTH);
+        assert_eq!(stringify(&items), raw.replace('a', "ab"));
+    }
+
+    #[test]
+    fn test_tree() {
+        const RAW: &str = "<?xml version=\"1.0\"?><a><b>1<c/></b>2<d/></a><!--e-->";
+
+        let document = parse_document(RAW).unwrap();
+        let mut tree = Tree::from(document.clone());
+        assert_eq!(tree.len(), 8);
+        assert_eq!(tree.roots().len(), 3);
+
+        let ids: Vec<NodeId> = tree.iter().map(|(id, _)| id).collect();
+        let [decl, a, b, one, c, two, d, comment] = ids[..] else {
+            panic!("Tree has wrong number of nodes.");
+        };
+        assert_eq!(tree.roots(), [decl, a, comment]);
+        assert_eq!(tree.children(a), [b, two, d]);
+        assert_eq!(tree.children(b), [one, c]);
+        assert!(tree.children(c).is_empty());
+
+        assert_eq!(tree.parent(c), Some(b));
+        assert_eq!(tree.parent(a), None);
+        assert_eq!(tree.ancestors(c).collect::<Vec<_>>(), [b, a]);
+        assert_eq!(tree.ancestors(a).count(), 0);
+
+        assert_eq!(tree.next_sibling(b), Some(two));
+        assert_eq!(tree.next_sibling(d), None);
+        assert_eq!(tree.prev_sibling(two), Some(b));
+        assert_eq!(tree.prev_sibling(b), None);
+        assert_eq!(tree.next_sibling(a), Some(comment));
+        assert_eq!(tree.prev_sibling(a), Some(decl));
+
+        // Elements in the tree have their children stored as nodes.
+        let Some(Item::Element(element)) = tree.get(b) else {
+            panic!("Item is of wrong type.");
+        };
+        assert_eq!(element.name, "b");
+        assert!(element.children.is_empty());
+
+        let Some(Item::Element(element)) = tree.get_mut(d) else {
+            panic!("Item is of wrong type.");
+        };
+        element.children.push(Item::Text(String::from("3")));
+        if let Some(Item::Text(text)) = tree.get_mut(one) {
+            text.push('!');
+        }
+
+        let document = Document::from_items(tree.into_items()).unwrap();
+        assert_eq!(
+            document.to_string(),
+            "<?xml version=\"1.0\"?><a><b>1!<c /></b>2<d>3</d></a><!--e-->"
+        );
+
+        // Conversion is lossless and deep nesting does not overflow the call stack.
+        let deep = "<a>".repeat(100_000) + &"</a>".repeat(100_000);
+        let items = parse(&deep).unwrap();
+        let tree = Tree::from(items.clone());
+        let last = tree.iter().last().map(|(id, _)| id).unwrap();
+        assert_eq!(tree.ancestors(last).count(), 99_999);
+        assert_eq!(tree.into_items(), items);
+    }
+
+    #[test]
+    fn test_paths() {
+        const RAW: &str = "<a><b>1<c>2</c></b><!--3--><d><e><f/>4</e></d>5</a>";
+
+        let mut items = parse(RAW).unwrap();
+        let Item::Element(a) = &mut items[0] else {
+            panic!("Item is of wrong type.");
+        };
+
+        // Every iterator returns the paths under which its items are found.
+        let depth_first: Vec<(NodePath, &Item)> = a.descendants().with_paths().collect();
+        assert_eq!(depth_first.len(), 10);
+        for (path, item) in a
+            .descendants()
+            .with_paths()
+            .chain(a.descendants_breadth_first().with_paths())
+            .chain(a.descendants_at_depth(3).with_paths())
+        {
+            assert_eq!(a.get_path(&path), Some(item), "{path:?}");
+        }
+        let paths: Vec<NodePath> = depth_first.into_iter().map(|(path, _)| path).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+
+        let breadth_first: Vec<Vec<usize>> = a
+            .descendants_breadth_first()
+            .with_paths()
+            .map(|(path, _)| path.0)
+            .collect();
+        assert_eq!(
+            breadth_first,
+            [
+                vec![0],
+                vec![1],
+                vec![2],
+                vec![3],
+                vec![0, 0],
+                vec![0, 1],
+                vec![2, 0],
+                vec![0, 1, 0],
+                vec![2, 0, 0],
+                vec![2, 0, 1],
+            ]
+        );
+
+        let texts: Vec<NodePath> = a
+            .descendants_mut()
+            .with_paths()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(texts[1], NodePath(vec![0, 1, 0]));
+        for path in &texts {
+            assert!(matches!(
+                a.get_path(path),
+                Some(Item::Text(_) | Item::Comment(_))
+            ));
+        }
+        let deep: Vec<NodePath> = a
+            .descendants_at_depth_mut(3)
+            .with_paths()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(
+            deep,
+            [
+                NodePath(vec![0, 1, 0]),
+                NodePath(vec![2, 0, 0]),
+                NodePath(vec![2, 0, 1])
+            ]
+        );
+
+        // Editing through paths.
+        let path = NodePath(vec![2, 0, 1]);
+        if let Some(Item::Text(text)) = a.get_path_mut(&path) {
+            text.push('!');
+        }
+        assert_eq!(path.parent(), Some(NodePath(vec![2, 0])));
+        assert_eq!(NodePath(vec![2]).parent(), None);
+        assert_eq!(NodePath(vec![2]).child(0), NodePath(vec![2, 0]));
+
+        assert!(matches!(
+            a.remove_at_path(&NodePath(vec![1])),
+            Some(Item::Comment(_))
+        ));
+        assert!(a.remove_at_path(&NodePath(vec![9])).is_none());
+        assert!(a
+            .insert_at_path(&NodePath(vec![1, 0, 2]), Item::Text(String::from("6")))
+            .is_ok());
+        assert!(a
+            .insert_at_path(&NodePath(vec![0, 3]), Item::Text(String::new()))
+            .is_err());
+        assert!(a
+            .insert_at_path(&NodePath(vec![0, 0, 0]), Item::Text(String::new()))
+            .is_err());
+        assert!(a
+            .insert_at_path(&NodePath::default(), Item::Text(String::new()))
+            .is_err());
+        assert!(a.get_path(&NodePath::default()).is_none());
+        assert_eq!(
+            a.to_string(),
+            "<a><b>1<c>2</c></b><d><e><f />4!6</e></d>5</a>"
+        );
+    }
+
+    /** Deterministic xorshift generator, so that failing inputs can be reproduced. */
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n.max(1) as u64) as usize
+        }
+    }
+
+    /** Mutate the input by inserting, removing, replacing or duplicating bytes and markup. */
+    fn mutate(rng: &mut Rng, input: &[u8]) -> Vec<u8> {
+        const TOKENS: &[&[u8]] = &[
+            b"<",
+            b">",
+            b"/>",
+            b"</",
+            b"&",
+            b";",
+            b"&a;",
+            b"&#x",
+            b"\"",
+            b"'",
+            b"=",
+            b"<!",
+            b"[",
+            b"]",
+            b"<?",
+            b"?>",
+            b"-->",
+            b"<!--",
+            b"<![CDATA[",
+            b"]]>",
+            b"xmlns:",
+            b"\xff",
+            b"\0",
+        ];
+
+        let mut output = input.to_vec();
+        for _ in 0..=rng.below(4) {
+            let at = rng.below(output.len() + 1);
+            match rng.below(5) {
+                0 => output.truncate(at),
+                1 => {
+                    let end = (at + rng.below(8)).min(output.len());
+                    output.drain(at..end);
+                }
+                2 if at < output.len() => output[at] = rng.next() as u8,
+                3 => {
+                    let start = rng.below(output.len());
+                    let end = (start + rng.below(16)).min(output.len());
+                    let copy = output[start..end].to_vec();
+                    output.splice(at..at, copy);
+                }
+                _ => {
+                    let token = TOKENS[rng.below(TOKENS.len())];
+                    output.splice(at..at, token.iter().copied());
+                }
+            }
+        }
+        output
+    }
+
+    /** Use the read-only API on every element, which must not panic. */
+    fn inspect(items: &[Item]) {
+        for item in items {
+            let _ = item.span();
+            let Item::Element(element) = item else {
+                continue;
+            };
+            let _ = element.get_text_content();
+            let _ = element.find_descendants(&|_| true);
+            for depth in 0..4 {
+                let _ = element.get_decendants_at_depth(depth);
+                let _ = element.descendants_at_depth(depth.into()).count();
+            }
+            let _ = element.descendants().count();
+            let _ = element.descendants_breadth_first().count();
+            let _ = (element.prefix(), element.local_name(), element.namespace());
+            let _ = (
+                element.span(),
+                element.content_span(),
+                element.end_tag_span(),
+            );
+            for index in 0..=element.children.len() {
+                let _ = element.child_span(index);
+            }
+            for (name, _) in element.attributes.iter() {
+                let _ = element.attribute_span(name);
+            }
+            inspect(&element.children);
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        const SEEDS: &[&str] = &[
+            r#"<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE a [<!ENTITY e "x&#38;y"><!ATTLIST a b CDATA "c">]><a b="1">&e;</a>"#,
+            r#"<a xmlns="urn:x" xmlns:p="urn:p"><p:b p:c="d"><!-- e --><?f g?></p:b><![CDATA[<h>]]>&amp;&#x41;</a>"#,
+            "<a>\n  <b>text</b>\n  <c/>\n</a>\n<!-- after -->",
+            r#"<!DOCTYPE a PUBLIC "p" 's'><a><b><c><d/></c></b></a>"#,
+        ];
+        // Lenient attributes may be malformed, and are then written back as they were read.
+        let options = [
+            (ParseOptions::new(), true),
+            (ParseOptions::new().trim_text(true).merge_cdata(true), true),
+            (
+                ParseOptions::new()
+                    .lenient_attributes(true)
+                    .raw_values(true),
+                false,
+            ),
+            (ParseOptions::new().namespaces(true).spans(true), true),
+            (
+                ParseOptions::new()
+                    .max_depth(2)
+                    .max_items(5)
+                    .max_text_length(3)
+                    .max_entity_expansion(2),
+                true,
+            ),
+        ];
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..2000 {
+            let seed = SEEDS[rng.below(SEEDS.len())];
+            let input = mutate(&mut rng, seed.as_bytes());
+            let (options, strict) = &options[rng.below(options.len())];
+
+            let _ = parse_reader_with(input.as_slice(), options);
+            let Ok(text) = std::str::from_utf8(&input) else {
+                continue;
+            };
+
+            if let Ok(items) = parse_with(text, options) {
+                inspect(&items);
+                // Whatever was read must be written in a way that can be read again.
+                let written = stringify(&items);
+                assert!(
+                    !strict || parse(&written).is_ok(),
+                    "{text:?} was written as {written:?}"
+                );
+            }
+            if let Ok(document) = parse_document_with(text, options) {
+                let _ = (document.xml_decl(), document.doctype());
+                assert!(!strict || parse_document(&document.to_string()).is_ok());
+            }
+            let _ = parse_with_warnings(text, options);
+
+            let _ = text.parse::<XmlDecl>();
+            let _ = text.parse::<DocType>();
+            let _ = ProcessingInstruction::new(text.to_owned(), text.to_owned());
+        }
     }
 }